@@ -0,0 +1,152 @@
+use super::{cipher::Address, family, notation::Formats};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::{net::IpAddr, sync::Arc};
+
+pub type Anonymizer = Arc<Option<PrefixPreserving>>;
+
+// The secret stays on the server, so anonymized addresses can't be reversed
+// by whoever sends them. Without one, the endpoints are turned off.
+pub fn create_anonymizer(secret: Option<String>) -> Anonymizer {
+    Arc::new(secret.and_then(|secret| PrefixPreserving::new(&secret).ok()))
+}
+
+#[derive(Deserialize)]
+pub struct AnonParams {
+    addr: String,
+    #[serde(flatten)]
+    formats: Formats,
+}
+
+pub async fn anon(
+    State(anonymizer): State<Anonymizer>,
+    Query(params): Query<AnonParams>,
+) -> Result<String, (StatusCode, String)> {
+    let anonymizer = enabled(&anonymizer)?;
+    let addr = match params.formats.parse(&params.addr)? {
+        IpAddr::V4(addr) => IpAddr::V4(anonymizer.anonymize(addr)),
+        IpAddr::V6(addr) => match addr.to_ipv4_mapped() {
+            Some(addr) => family::restore(anonymizer.anonymize(addr), true),
+            None => IpAddr::V6(anonymizer.anonymize(addr)),
+        },
+    };
+
+    Ok(params.formats.output().render(addr))
+}
+
+pub async fn deanon(
+    State(anonymizer): State<Anonymizer>,
+    Query(params): Query<AnonParams>,
+) -> Result<String, (StatusCode, String)> {
+    let anonymizer = enabled(&anonymizer)?;
+    let addr = match params.formats.parse(&params.addr)? {
+        IpAddr::V4(addr) => IpAddr::V4(anonymizer.deanonymize(addr)),
+        IpAddr::V6(addr) => match addr.to_ipv4_mapped() {
+            Some(addr) => family::restore(anonymizer.deanonymize(addr), true),
+            None => IpAddr::V6(anonymizer.deanonymize(addr)),
+        },
+    };
+
+    Ok(params.formats.output().render(addr))
+}
+
+fn enabled(
+    anonymizer: &Option<PrefixPreserving>,
+) -> Result<&PrefixPreserving, (StatusCode, String)> {
+    anonymizer.as_ref().ok_or((
+        StatusCode::FORBIDDEN,
+        "anonymization is not enabled".to_string(),
+    ))
+}
+
+// Crypto-PAn construction: every bit is flipped by a keyed pseudorandom
+// function of the bits before it, so addresses sharing a prefix of any length
+// still share one after anonymization. HMAC-SHA256 takes the place of AES.
+pub struct PrefixPreserving {
+    mac: Hmac<Sha256>,
+}
+
+impl PrefixPreserving {
+    pub fn new(secret: &str) -> Result<Self, (StatusCode, String)> {
+        if secret.is_empty() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "secret must not be empty".to_string(),
+            ));
+        }
+
+        let mac = Hmac::new_from_slice(secret.as_bytes()).unwrap();
+        Ok(Self { mac })
+    }
+
+    pub fn anonymize<A: Address>(&self, addr: A) -> A {
+        let bits = addr.to_bits();
+        let flips = (0..A::BITS).fold(0, |flips, position| flips | self.flip::<A>(position, bits));
+
+        A::from_bits(bits ^ flips)
+    }
+
+    pub fn deanonymize<A: Address>(&self, addr: A) -> A {
+        let bits = (0..A::BITS).fold(addr.to_bits(), |bits, position| {
+            bits ^ self.flip::<A>(position, bits)
+        });
+
+        A::from_bits(bits)
+    }
+
+    // The flip of a bit only depends on the bits above it, which are already
+    // restored when deanonymizing from the most significant bit downwards.
+    fn flip<A: Address>(&self, position: u32, bits: u128) -> u128 {
+        let shift = A::BITS - position;
+        let prefix = bits.checked_shr(shift).unwrap_or(0);
+
+        let mut mac = self.mac.clone();
+        mac.update(&A::BITS.to_be_bytes());
+        mac.update(&position.to_be_bytes());
+        mac.update(&prefix.to_be_bytes());
+        let digest = mac.finalize().into_bytes();
+
+        u128::from(digest[0] >> 7) << (shift - 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    #[test]
+    fn deanonymize_inverts_anonymize() {
+        let anonymizer = PrefixPreserving::new("secret").unwrap();
+
+        for addr in ["0.0.0.0", "10.1.2.3", "255.255.255.255"] {
+            let addr: Ipv4Addr = addr.parse().unwrap();
+            assert_eq!(anonymizer.deanonymize(anonymizer.anonymize(addr)), addr);
+        }
+        for addr in ["::", "2001:db8::1", "fe80::ffff:1"] {
+            let addr: Ipv6Addr = addr.parse().unwrap();
+            assert_eq!(anonymizer.deanonymize(anonymizer.anonymize(addr)), addr);
+        }
+    }
+
+    #[test]
+    fn anonymize_preserves_shared_prefixes() {
+        let anonymizer = PrefixPreserving::new("secret").unwrap();
+        let x = anonymizer.anonymize("10.1.2.3".parse::<Ipv4Addr>().unwrap());
+        let y = anonymizer.anonymize("10.1.3.3".parse::<Ipv4Addr>().unwrap());
+
+        // The inputs share their first 23 bits, and differ in the 24th.
+        assert_eq!(u32::from(x) >> 9, u32::from(y) >> 9);
+        assert_ne!(u32::from(x) >> 8, u32::from(y) >> 8);
+    }
+
+    #[test]
+    fn empty_secret_is_rejected() {
+        assert!(PrefixPreserving::new("").is_err());
+    }
+}
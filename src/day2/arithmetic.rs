@@ -0,0 +1,179 @@
+use super::{
+    cipher::Algorithm,
+    family::{self, AddrPair, IncompatibleFamilies},
+    notation::Format,
+};
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    net::{IpAddr, Ipv4Addr},
+};
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Overflow {
+    #[default]
+    Wrap,
+    Saturate,
+    Error,
+}
+
+pub enum ArithmeticError {
+    Overflowed(Vec<usize>),
+    Unsupported,
+}
+
+impl Display for ArithmeticError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArithmeticError::Overflowed(octets) => {
+                let noun = if octets.len() == 1 { "octet" } else { "octets" };
+                write!(f, "{noun} {} overflowed", octets.iter().join(", "))
+            }
+            ArithmeticError::Unsupported => write!(
+                f,
+                "overflow policies only apply to single IPv4 addresses with the add algorithm"
+            ),
+        }
+    }
+}
+
+impl From<ArithmeticError> for (StatusCode, String) {
+    fn from(value: ArithmeticError) -> Self {
+        (StatusCode::BAD_REQUEST, value.to_string())
+    }
+}
+
+pub struct Checked {
+    result: IpAddr,
+    overflowed: Option<Vec<usize>>,
+}
+
+#[derive(Serialize)]
+struct CheckedBody {
+    result: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    overflowed: Option<Vec<usize>>,
+}
+
+impl Checked {
+    pub fn respond(self, headers: &HeaderMap, format: Format) -> Response {
+        let accepts_json = headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .is_some_and(|accept| accept.contains("application/json"));
+
+        let result = format.render(self.result);
+        if accepts_json {
+            Json(CheckedBody {
+                result,
+                overflowed: self.overflowed,
+            })
+            .into_response()
+        } else {
+            result.into_response()
+        }
+    }
+}
+
+pub fn encrypt(
+    from: IpAddr,
+    key: IpAddr,
+    algo: Option<Algorithm>,
+    overflow: Option<Overflow>,
+) -> Result<Checked, (StatusCode, String)> {
+    check(from, key, algo, overflow, Operation::Add, family::encrypt)
+}
+
+pub fn derive_key(
+    from: IpAddr,
+    to: IpAddr,
+    algo: Option<Algorithm>,
+    overflow: Option<Overflow>,
+) -> Result<Checked, (StatusCode, String)> {
+    check(from, to, algo, overflow, Operation::Sub, family::derive_key)
+}
+
+#[derive(Clone, Copy)]
+enum Operation {
+    Add,
+    Sub,
+}
+
+impl Operation {
+    // The key is `to - from`, while the operands arrive as (from, to).
+    fn apply(self, from: u8, other: u8) -> (u8, bool) {
+        match self {
+            Operation::Add => from.overflowing_add(other),
+            Operation::Sub => other.overflowing_sub(from),
+        }
+    }
+
+    fn saturated(self) -> u8 {
+        match self {
+            Operation::Add => u8::MAX,
+            Operation::Sub => u8::MIN,
+        }
+    }
+}
+
+// IPv4 addition is done octet by octet here to see which octets overflowed;
+// every other combination falls back to the cipher without overflow details.
+fn check(
+    x: IpAddr,
+    y: IpAddr,
+    algo: Option<Algorithm>,
+    overflow: Option<Overflow>,
+    operation: Operation,
+    fallback: fn(IpAddr, IpAddr, Option<Algorithm>) -> Result<IpAddr, IncompatibleFamilies>,
+) -> Result<Checked, (StatusCode, String)> {
+    match (family::pair(x, y)?, algo.unwrap_or(Algorithm::Add)) {
+        (AddrPair::V4 { x, y, mapped }, Algorithm::Add) => {
+            let (addr, overflowed) = octetwise(x, y, overflow.unwrap_or_default(), operation)?;
+            Ok(Checked {
+                result: family::restore(addr, mapped),
+                overflowed: Some(overflowed),
+            })
+        }
+        _ if overflow.is_some() => Err(ArithmeticError::Unsupported.into()),
+        _ => Ok(Checked {
+            result: fallback(x, y, algo)?,
+            overflowed: None,
+        }),
+    }
+}
+
+fn octetwise(
+    x: Ipv4Addr,
+    y: Ipv4Addr,
+    overflow: Overflow,
+    operation: Operation,
+) -> Result<(Ipv4Addr, Vec<usize>), ArithmeticError> {
+    let x = x.octets();
+    let y = y.octets();
+
+    let mut octets = [0; 4];
+    let mut overflowed = vec![];
+    for i in 0..4 {
+        let (octet, is_overflowed) = operation.apply(x[i], y[i]);
+        octets[i] = octet;
+
+        if is_overflowed {
+            overflowed.push(i);
+            if let Overflow::Saturate = overflow {
+                octets[i] = operation.saturated();
+            }
+        }
+    }
+
+    match overflow {
+        Overflow::Error if !overflowed.is_empty() => Err(ArithmeticError::Overflowed(overflowed)),
+        _ => Ok((Ipv4Addr::from(octets), overflowed)),
+    }
+}
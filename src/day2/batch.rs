@@ -0,0 +1,226 @@
+use super::{cipher::Algorithm, family};
+use axum::{
+    body::{Body, BodyDataStream},
+    extract::Query,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures::{
+    future,
+    stream::{self, BoxStream},
+    Stream, StreamExt,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{convert::Infallible, net::IpAddr};
+
+// The most of a body held in memory at once, the same as axum's default body
+// limit.
+const MAX_BUFFERED: usize = 2 * 1024 * 1024;
+
+type Records<R> = BoxStream<'static, Result<R, String>>;
+
+#[derive(Deserialize)]
+pub struct BatchParams {
+    algo: Option<Algorithm>,
+}
+
+#[derive(Deserialize)]
+struct DestRecord {
+    from: IpAddr,
+    key: IpAddr,
+}
+
+#[derive(Deserialize)]
+struct KeyRecord {
+    from: IpAddr,
+    to: IpAddr,
+}
+
+#[derive(Serialize)]
+struct Line {
+    index: usize,
+    #[serde(flatten)]
+    outcome: Outcome,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Outcome {
+    Result(IpAddr),
+    Error(String),
+}
+
+pub async fn dest(
+    Query(params): Query<BatchParams>,
+    body: Body,
+) -> Result<Response, (StatusCode, String)> {
+    let records = parse_records(body).await?;
+
+    Ok(respond(records, move |DestRecord { from, key }| {
+        family::encrypt(from, key, params.algo)
+    }))
+}
+
+pub async fn key(
+    Query(params): Query<BatchParams>,
+    body: Body,
+) -> Result<Response, (StatusCode, String)> {
+    let records = parse_records(body).await?;
+
+    Ok(respond(records, move |KeyRecord { from, to }| {
+        family::derive_key(from, to, params.algo)
+    }))
+}
+
+// A JSON array is read and decoded as a whole, anything else is streamed as
+// one record per line. Records that fail to decode are reported alongside the
+// others.
+async fn parse_records<R: DeserializeOwned + Send + 'static>(
+    body: Body,
+) -> Result<Records<R>, (StatusCode, String)> {
+    let mut chunks = body.into_data_stream();
+    let mut buffer = Vec::<u8>::new();
+
+    // Only as much is read as it takes to tell the two forms apart.
+    let first = loop {
+        if let Some(&byte) = buffer.iter().find(|byte| !byte.is_ascii_whitespace()) {
+            break Some(byte);
+        }
+        // Leading whitespace means nothing in either form.
+        buffer.clear();
+        match chunks.next().await {
+            Some(chunk) => buffer.extend_from_slice(&chunk.map_err(read_error)?),
+            None => break None,
+        }
+    };
+
+    if first != Some(b'[') {
+        let records = lines(chunks, buffer)
+            .filter(|line| future::ready(!matches!(line, Ok(line) if line.trim_ascii().is_empty())))
+            .map(|line| {
+                line.and_then(|line| {
+                    serde_json::from_slice(&line).map_err(|error| error.to_string())
+                })
+            });
+        return Ok(records.boxed());
+    }
+
+    while let Some(chunk) = chunks.next().await {
+        buffer.extend_from_slice(&chunk.map_err(read_error)?);
+        if buffer.len() > MAX_BUFFERED {
+            return Err((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("JSON arrays are limited to {MAX_BUFFERED} bytes"),
+            ));
+        }
+    }
+    let values = serde_json::from_slice::<Vec<Value>>(&buffer)
+        .map_err(|error| (StatusCode::BAD_REQUEST, error.to_string()))?;
+    let records = values
+        .into_iter()
+        .map(|value| serde_json::from_value(value).map_err(|error| error.to_string()))
+        .collect::<Vec<_>>();
+
+    Ok(stream::iter(records).boxed())
+}
+
+// Splits the body into lines as it arrives. A body that can't be read, or a
+// line that's too long, ends the stream with an error.
+fn lines(chunks: BodyDataStream, buffer: Vec<u8>) -> impl Stream<Item = Result<Vec<u8>, String>> {
+    stream::unfold(Some((chunks, buffer)), |state| async move {
+        let (mut chunks, mut buffer) = state?;
+        loop {
+            if let Some(end) = buffer.iter().position(|&byte| byte == b'\n') {
+                let line = buffer.drain(..=end).collect();
+                return Some((Ok(line), Some((chunks, buffer))));
+            }
+            if buffer.len() > MAX_BUFFERED {
+                let error = format!("lines are limited to {MAX_BUFFERED} bytes");
+                return Some((Err(error), None));
+            }
+            match chunks.next().await {
+                Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+                Some(Err(error)) => return Some((Err(error.to_string()), None)),
+                None if buffer.is_empty() => return None,
+                None => return Some((Ok(buffer), None)),
+            }
+        }
+    })
+}
+
+fn read_error(error: axum::Error) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, error.to_string())
+}
+
+fn respond<R, E>(
+    records: Records<R>,
+    transform: impl Fn(R) -> Result<IpAddr, E> + Send + 'static,
+) -> Response
+where
+    R: Send + 'static,
+    E: ToString,
+{
+    let lines = records.enumerate().map(move |(index, record)| {
+        let outcome = record
+            .and_then(|record| transform(record).map_err(|error| error.to_string()))
+            .map_or_else(Outcome::Error, Outcome::Result);
+
+        let line = serde_json::to_string(&Line { index, outcome }).unwrap();
+        Ok::<_, Infallible>(line + "\n")
+    });
+
+    (
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    fn body(chunks: Vec<&'static str>) -> Body {
+        let chunks = chunks.into_iter().map(Ok::<_, Infallible>);
+        Body::from_stream(stream::iter(chunks))
+    }
+
+    async fn records(chunks: &[&'static str]) -> Vec<Result<KeyRecord, String>> {
+        let records = parse_records(body(chunks.to_vec())).await.ok().unwrap();
+        records.collect().await
+    }
+
+    #[test]
+    fn lines_are_split_across_chunks() {
+        let records = block_on(records(&[
+            "\n{\"from\": \"10.0.0.1\", ",
+            "\"to\": \"10.0.0.2\"}\n\nnot json\n{\"from\": \"::1\", \"to\"",
+            ": \"::2\"}",
+        ]));
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records[0].as_ref().unwrap().to,
+            "10.0.0.2".parse::<IpAddr>().unwrap()
+        );
+        assert!(records[1].is_err());
+        assert_eq!(
+            records[2].as_ref().unwrap().from,
+            "::1".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn arrays_are_decoded_whole() {
+        let records = block_on(records(&[
+            " [{\"from\": \"10.0.0.1\", ",
+            "\"to\": \"10.0.0.2\"}, 1]",
+        ]));
+
+        assert_eq!(records.len(), 2);
+        assert!(records[0].is_ok());
+        assert!(records[1].is_err());
+    }
+}
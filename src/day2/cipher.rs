@@ -0,0 +1,305 @@
+use serde::Deserialize;
+use std::{
+    net::{Ipv4Addr, Ipv6Addr},
+    ops::BitXor,
+};
+
+pub trait AddressCipher {
+    fn encrypt<A: Address>(&self, from: A, key: A) -> A;
+    fn derive_key<A: Address>(&self, from: A, to: A) -> A;
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+    Add,
+    Xor,
+    Rotate,
+    Feistel,
+}
+
+impl AddressCipher for Algorithm {
+    fn encrypt<A: Address>(&self, from: A, key: A) -> A {
+        match self {
+            Algorithm::Add => WrappingAdd.encrypt(from, key),
+            Algorithm::Xor => Xor.encrypt(from, key),
+            Algorithm::Rotate => Rotation.encrypt(from, key),
+            Algorithm::Feistel => Feistel.encrypt(from, key),
+        }
+    }
+
+    fn derive_key<A: Address>(&self, from: A, to: A) -> A {
+        match self {
+            Algorithm::Add => WrappingAdd.derive_key(from, to),
+            Algorithm::Xor => Xor.derive_key(from, to),
+            Algorithm::Rotate => Rotation.derive_key(from, to),
+            Algorithm::Feistel => Feistel.derive_key(from, to),
+        }
+    }
+}
+
+pub struct WrappingAdd;
+
+impl AddressCipher for WrappingAdd {
+    fn encrypt<A: Address>(&self, from: A, key: A) -> A {
+        zip_lanes(from, key, Lane::wrapping_add)
+    }
+
+    fn derive_key<A: Address>(&self, from: A, to: A) -> A {
+        zip_lanes(to, from, Lane::wrapping_sub)
+    }
+}
+
+pub struct Xor;
+
+impl AddressCipher for Xor {
+    fn encrypt<A: Address>(&self, from: A, key: A) -> A {
+        zip_lanes(from, key, BitXor::bitxor)
+    }
+
+    fn derive_key<A: Address>(&self, from: A, to: A) -> A {
+        zip_lanes(from, to, BitXor::bitxor)
+    }
+}
+
+pub struct Rotation;
+
+impl AddressCipher for Rotation {
+    fn encrypt<A: Address>(&self, from: A, key: A) -> A {
+        zip_lanes(from, key, |from, key| {
+            from.wrapping_add(key).rotate_left(A::Lane::ROTATION)
+        })
+    }
+
+    fn derive_key<A: Address>(&self, from: A, to: A) -> A {
+        zip_lanes(to, from, |to, from| {
+            to.rotate_right(A::Lane::ROTATION).wrapping_sub(from)
+        })
+    }
+}
+
+// The key whitens the input of a fixed Feistel network, so the key stays
+// recoverable from any (from, to) pair by running the network backwards.
+pub struct Feistel;
+
+impl Feistel {
+    const ROUNDS: u8 = 4;
+
+    fn round<L: Lane>(half: &[L], round: u8) -> Vec<L> {
+        let constant = L::from_u8(round.wrapping_mul(0x9d) ^ 0x5a);
+        (0..half.len())
+            .map(|i| {
+                let next = half[(i + 1) % half.len()];
+                (half[i].wrapping_add(next) ^ constant).rotate_left(L::ROTATION)
+            })
+            .collect()
+    }
+
+    fn permute<L: Lane>(lanes: Vec<L>) -> Vec<L> {
+        let (left, right) = lanes.split_at(lanes.len() / 2);
+        let (mut left, mut right) = (left.to_vec(), right.to_vec());
+
+        for round in 0..Self::ROUNDS {
+            let mixed = xor_lanes(&left, &Self::round(&right, round));
+            left = right;
+            right = mixed;
+        }
+
+        left.into_iter().chain(right).collect()
+    }
+
+    fn unpermute<L: Lane>(lanes: Vec<L>) -> Vec<L> {
+        let (left, right) = lanes.split_at(lanes.len() / 2);
+        let (mut left, mut right) = (left.to_vec(), right.to_vec());
+
+        for round in (0..Self::ROUNDS).rev() {
+            let mixed = xor_lanes(&right, &Self::round(&left, round));
+            right = left;
+            left = mixed;
+        }
+
+        left.into_iter().chain(right).collect()
+    }
+}
+
+impl AddressCipher for Feistel {
+    fn encrypt<A: Address>(&self, from: A, key: A) -> A {
+        let whitened = Xor.encrypt(from, key);
+        A::from_lanes(Self::permute(whitened.lanes()))
+    }
+
+    fn derive_key<A: Address>(&self, from: A, to: A) -> A {
+        let whitened = A::from_lanes(Self::unpermute(to.lanes()));
+        Xor.derive_key(from, whitened)
+    }
+}
+
+pub trait Lane: Copy + BitXor<Output = Self> {
+    const ROTATION: u32;
+
+    fn wrapping_add(self, rhs: Self) -> Self;
+    fn wrapping_sub(self, rhs: Self) -> Self;
+    fn rotate_left(self, n: u32) -> Self;
+    fn rotate_right(self, n: u32) -> Self;
+    fn from_u8(n: u8) -> Self;
+}
+
+macro_rules! impl_lane {
+    ($lane:ty, $rotation:expr) => {
+        impl Lane for $lane {
+            const ROTATION: u32 = $rotation;
+
+            fn wrapping_add(self, rhs: Self) -> Self {
+                <$lane>::wrapping_add(self, rhs)
+            }
+
+            fn wrapping_sub(self, rhs: Self) -> Self {
+                <$lane>::wrapping_sub(self, rhs)
+            }
+
+            fn rotate_left(self, n: u32) -> Self {
+                <$lane>::rotate_left(self, n)
+            }
+
+            fn rotate_right(self, n: u32) -> Self {
+                <$lane>::rotate_right(self, n)
+            }
+
+            fn from_u8(n: u8) -> Self {
+                n.into()
+            }
+        }
+    };
+}
+
+impl_lane!(u8, 3);
+impl_lane!(u16, 5);
+
+pub trait Address: Copy {
+    type Lane: Lane;
+    const BITS: u32;
+
+    fn lanes(self) -> Vec<Self::Lane>;
+    fn from_lanes(lanes: Vec<Self::Lane>) -> Self;
+    fn to_bits(self) -> u128;
+    fn from_bits(bits: u128) -> Self;
+}
+
+impl Address for Ipv4Addr {
+    type Lane = u8;
+    const BITS: u32 = 32;
+
+    fn lanes(self) -> Vec<u8> {
+        self.octets().to_vec()
+    }
+
+    fn from_lanes(lanes: Vec<u8>) -> Self {
+        let octets: [u8; 4] = lanes.try_into().unwrap();
+        Ipv4Addr::from(octets)
+    }
+
+    fn to_bits(self) -> u128 {
+        u32::from(self).into()
+    }
+
+    fn from_bits(bits: u128) -> Self {
+        Ipv4Addr::from(bits as u32)
+    }
+}
+
+impl Address for Ipv6Addr {
+    type Lane = u16;
+    const BITS: u32 = 128;
+
+    fn lanes(self) -> Vec<u16> {
+        self.segments().to_vec()
+    }
+
+    fn from_lanes(lanes: Vec<u16>) -> Self {
+        let segments: [u16; 8] = lanes.try_into().unwrap();
+        Ipv6Addr::from(segments)
+    }
+
+    fn to_bits(self) -> u128 {
+        u128::from(self)
+    }
+
+    fn from_bits(bits: u128) -> Self {
+        Ipv6Addr::from(bits)
+    }
+}
+
+fn zip_lanes<A: Address>(x: A, y: A, f: impl Fn(A::Lane, A::Lane) -> A::Lane) -> A {
+    let lanes = x
+        .lanes()
+        .into_iter()
+        .zip(y.lanes())
+        .map(|(x, y)| f(x, y))
+        .collect();
+    A::from_lanes(lanes)
+}
+
+fn xor_lanes<L: Lane>(x: &[L], y: &[L]) -> Vec<L> {
+    x.iter().zip(y).map(|(&x, &y)| x ^ y).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGORITHMS: [Algorithm; 4] = [
+        Algorithm::Add,
+        Algorithm::Xor,
+        Algorithm::Rotate,
+        Algorithm::Feistel,
+    ];
+
+    fn v4(s: &str) -> Ipv4Addr {
+        s.parse().unwrap()
+    }
+
+    fn v6(s: &str) -> Ipv6Addr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn derive_key_recovers_ipv4_key() {
+        let pairs = [
+            ("10.0.0.0", "1.2.3.255"),
+            ("255.255.255.255", "255.255.255.255"),
+            ("0.0.0.0", "128.64.32.16"),
+            ("192.168.1.1", "0.0.0.0"),
+        ];
+
+        for algo in ALGORITHMS {
+            for (from, key) in pairs.map(|(from, key)| (v4(from), v4(key))) {
+                assert_eq!(algo.derive_key(from, algo.encrypt(from, key)), key);
+            }
+        }
+    }
+
+    #[test]
+    fn derive_key_recovers_ipv6_key() {
+        let pairs = [
+            ("fe80::1", "5:6:7::3333"),
+            ("ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff", "::1"),
+            ("::", "aaaa::ffff"),
+            ("2001:db8::", "::"),
+        ];
+
+        for algo in ALGORITHMS {
+            for (from, key) in pairs.map(|(from, key)| (v6(from), v6(key))) {
+                assert_eq!(algo.derive_key(from, algo.encrypt(from, key)), key);
+            }
+        }
+    }
+
+    #[test]
+    fn unpermute_inverts_permute() {
+        let lanes = vec![0x01u8, 0x80, 0xff, 0x3c];
+        assert_eq!(Feistel::unpermute(Feistel::permute(lanes.clone())), lanes);
+
+        let lanes = vec![0x0001u16, 0x8000, 0xffff, 0x1234, 0, 7, 0xabcd, 0x4242];
+        assert_eq!(Feistel::unpermute(Feistel::permute(lanes.clone())), lanes);
+    }
+}
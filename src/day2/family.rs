@@ -0,0 +1,95 @@
+use super::cipher::{AddressCipher, Algorithm};
+use axum::http::StatusCode;
+use std::{
+    fmt::Display,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+pub enum AddrPair {
+    V4 {
+        x: Ipv4Addr,
+        y: Ipv4Addr,
+        mapped: bool,
+    },
+    V6 {
+        x: Ipv6Addr,
+        y: Ipv6Addr,
+    },
+}
+
+pub struct IncompatibleFamilies {
+    x: IpAddr,
+    y: IpAddr,
+}
+
+impl Display for IncompatibleFamilies {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} and {} belong to different address families; \
+            IPv4 addresses can only be combined with IPv4 or IPv4-mapped IPv6 (::ffff:a.b.c.d) addresses",
+            self.x, self.y
+        )
+    }
+}
+
+impl From<IncompatibleFamilies> for (StatusCode, String) {
+    fn from(value: IncompatibleFamilies) -> Self {
+        (StatusCode::BAD_REQUEST, value.to_string())
+    }
+}
+
+// IPv4-mapped IPv6 addresses are reduced to their embedded IPv4 part, and
+// `mapped` records whether the result should be mapped back, following `x`.
+pub fn pair(x: IpAddr, y: IpAddr) -> Result<AddrPair, IncompatibleFamilies> {
+    match (x, y, to_ipv4(x), to_ipv4(y)) {
+        (_, _, Some(v4_x), Some(v4_y)) => Ok(AddrPair::V4 {
+            x: v4_x,
+            y: v4_y,
+            mapped: x.is_ipv6(),
+        }),
+        (IpAddr::V6(x), IpAddr::V6(y), None, None) => Ok(AddrPair::V6 { x, y }),
+        _ => Err(IncompatibleFamilies { x, y }),
+    }
+}
+
+pub fn encrypt(
+    from: IpAddr,
+    key: IpAddr,
+    algo: Option<Algorithm>,
+) -> Result<IpAddr, IncompatibleFamilies> {
+    Ok(match pair(from, key)? {
+        AddrPair::V4 { x, y, mapped } => {
+            restore(algo.unwrap_or(Algorithm::Add).encrypt(x, y), mapped)
+        }
+        AddrPair::V6 { x, y } => IpAddr::V6(algo.unwrap_or(Algorithm::Xor).encrypt(x, y)),
+    })
+}
+
+pub fn derive_key(
+    from: IpAddr,
+    to: IpAddr,
+    algo: Option<Algorithm>,
+) -> Result<IpAddr, IncompatibleFamilies> {
+    Ok(match pair(from, to)? {
+        AddrPair::V4 { x, y, mapped } => {
+            restore(algo.unwrap_or(Algorithm::Add).derive_key(x, y), mapped)
+        }
+        AddrPair::V6 { x, y } => IpAddr::V6(algo.unwrap_or(Algorithm::Xor).derive_key(x, y)),
+    })
+}
+
+pub fn restore(addr: Ipv4Addr, mapped: bool) -> IpAddr {
+    if mapped {
+        IpAddr::V6(addr.to_ipv6_mapped())
+    } else {
+        IpAddr::V4(addr)
+    }
+}
+
+fn to_ipv4(addr: IpAddr) -> Option<Ipv4Addr> {
+    match addr {
+        IpAddr::V4(addr) => Some(addr),
+        IpAddr::V6(addr) => addr.to_ipv4_mapped(),
+    }
+}
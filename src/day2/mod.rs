@@ -0,0 +1,210 @@
+pub mod anon;
+mod arithmetic;
+pub mod batch;
+pub mod cipher;
+mod family;
+mod notation;
+pub mod pcap;
+mod subnet;
+
+pub mod task1 {
+    use std::net::IpAddr;
+
+    use super::{
+        arithmetic::{self, ArithmeticError, Overflow},
+        cipher::Algorithm,
+        family::{self, AddrPair},
+        notation::Formats,
+        subnet::{self, Network, Page, Subject},
+    };
+    use axum::{
+        extract::Query,
+        http::{HeaderMap, StatusCode},
+        response::Response,
+    };
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    pub struct AddrKey {
+        from: String,
+        key: String,
+        algo: Option<Algorithm>,
+        overflow: Option<Overflow>,
+        #[serde(default, deserialize_with = "subnet::parse_index")]
+        offset: Option<u128>,
+        #[serde(default, deserialize_with = "subnet::parse_index")]
+        limit: Option<u128>,
+        #[serde(flatten)]
+        formats: Formats,
+    }
+
+    pub async fn dest(
+        address_key: Query<AddrKey>,
+        headers: HeaderMap,
+    ) -> Result<Response, (StatusCode, String)> {
+        let AddrKey {
+            from,
+            key,
+            algo,
+            overflow,
+            offset,
+            limit,
+            formats,
+        } = address_key.0;
+        let from = Subject::<IpAddr>::parse(&from, &formats)?;
+        let key: IpAddr = formats.parse(&key)?;
+        let format = formats.output();
+
+        match from {
+            Subject::Host(from) => {
+                let (from, key) = (formats.settle(from, key), formats.settle(key, from));
+                let dest = arithmetic::encrypt(from, key, algo, overflow)?;
+                Ok(dest.respond(&headers, format))
+            }
+            Subject::Network { .. } if overflow.is_some() => {
+                Err(ArithmeticError::Unsupported.into())
+            }
+            Subject::Network { addr, prefix } => {
+                let page = Page::new(offset, limit);
+                let (addr, key) = (formats.settle(addr, key), formats.settle(key, addr));
+
+                match family::pair(addr, key)? {
+                    AddrPair::V4 { x, y, mapped } => {
+                        let network = Network::from_mapped(x, prefix, mapped)?;
+                        let algo = algo.unwrap_or(Algorithm::Add);
+                        let render = move |addr| family::restore(addr, mapped);
+                        Ok(network.encrypt(y, algo, page, render, format)?)
+                    }
+                    AddrPair::V6 { x, y } => {
+                        let network = Network::new(x, prefix)?;
+                        let algo = algo.unwrap_or(Algorithm::Xor);
+                        Ok(network.encrypt(y, algo, page, IpAddr::V6, format)?)
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub mod task2 {
+    use std::net::IpAddr;
+
+    use super::{
+        arithmetic::{self, Overflow},
+        cipher::Algorithm,
+        notation::Formats,
+    };
+    use axum::{
+        extract::Query,
+        http::{HeaderMap, StatusCode},
+        response::Response,
+    };
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    pub struct KeyParams {
+        from: String,
+        to: String,
+        algo: Option<Algorithm>,
+        overflow: Option<Overflow>,
+        #[serde(flatten)]
+        formats: Formats,
+    }
+
+    pub async fn key(
+        param: Query<KeyParams>,
+        headers: HeaderMap,
+    ) -> Result<Response, (StatusCode, String)> {
+        let KeyParams {
+            from,
+            to,
+            algo,
+            overflow,
+            formats,
+        } = param.0;
+        let from: IpAddr = formats.parse(&from)?;
+        let to: IpAddr = formats.parse(&to)?;
+        let (from, to) = (formats.settle(from, to), formats.settle(to, from));
+
+        let key = arithmetic::derive_key(from, to, algo, overflow)?;
+        Ok(key.respond(&headers, formats.output()))
+    }
+}
+
+pub mod task3 {
+    use super::{
+        cipher::{AddressCipher, Algorithm},
+        notation::Formats,
+        subnet::{self, Network, Page, Subject},
+    };
+    use axum::{
+        extract::Query,
+        http::StatusCode,
+        response::{IntoResponse, Response},
+    };
+    use serde::Deserialize;
+    use std::net::{IpAddr, Ipv6Addr};
+
+    #[derive(Deserialize)]
+    pub struct DestParams {
+        from: String,
+        key: String,
+        algo: Option<Algorithm>,
+        #[serde(default, deserialize_with = "subnet::parse_index")]
+        offset: Option<u128>,
+        #[serde(default, deserialize_with = "subnet::parse_index")]
+        limit: Option<u128>,
+        #[serde(flatten)]
+        formats: Formats,
+    }
+
+    pub async fn dest(params: Query<DestParams>) -> Result<Response, (StatusCode, String)> {
+        let DestParams {
+            from,
+            key,
+            algo,
+            offset,
+            limit,
+            formats,
+        } = params.0;
+        let from = Subject::<Ipv6Addr>::parse(&from, &formats)?;
+        let key: Ipv6Addr = formats.parse(&key)?;
+        let algo = algo.unwrap_or(Algorithm::Xor);
+        let format = formats.output();
+
+        match from {
+            Subject::Host(from) => {
+                let dest = IpAddr::V6(algo.encrypt(from, key));
+                Ok(format.render(dest).into_response())
+            }
+            Subject::Network { addr, prefix } => {
+                let network = Network::new(addr, prefix)?;
+                let page = Page::new(offset, limit);
+                Ok(network.encrypt(key, algo, page, IpAddr::V6, format)?)
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    pub struct KeyParams {
+        from: String,
+        to: String,
+        algo: Option<Algorithm>,
+        #[serde(flatten)]
+        formats: Formats,
+    }
+
+    pub async fn key(params: Query<KeyParams>) -> Result<String, (StatusCode, String)> {
+        let KeyParams {
+            from,
+            to,
+            algo,
+            formats,
+        } = params.0;
+        let from: Ipv6Addr = formats.parse(&from)?;
+        let to: Ipv6Addr = formats.parse(&to)?;
+
+        let key = algo.unwrap_or(Algorithm::Xor).derive_key(from, to);
+        Ok(formats.output().render(IpAddr::V6(key)))
+    }
+}
@@ -0,0 +1,154 @@
+use axum::http::StatusCode;
+use serde::Deserialize;
+use std::{
+    fmt::Display,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    #[default]
+    Text,
+    Integer,
+    Hex,
+    Binary,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Family {
+    V4,
+    V6,
+}
+
+#[derive(Deserialize)]
+pub struct Formats {
+    format: Option<Format>,
+    input_format: Option<Format>,
+    output_format: Option<Format>,
+    family: Option<Family>,
+}
+
+impl Formats {
+    pub fn input(&self) -> Format {
+        self.input_format.or(self.format).unwrap_or_default()
+    }
+
+    pub fn parse<A: Notation>(&self, s: &str) -> Result<A, InvalidNotation> {
+        let width = self.family.map(|family| match family {
+            Family::V4 => 32,
+            Family::V6 => 128,
+        });
+        self.input().parse_as(s, width)
+    }
+
+    // Without `family`, an integer up to u32::MAX is read as IPv4, unless the
+    // address it's combined with is plain IPv6, which it then follows.
+    pub fn settle(&self, addr: IpAddr, other: IpAddr) -> IpAddr {
+        let other_is_v6 = matches!(other, IpAddr::V6(other) if other.to_ipv4_mapped().is_none());
+
+        match (addr, self.input(), self.family) {
+            (IpAddr::V4(addr), Format::Integer, None) if other_is_v6 => {
+                IpAddr::V6(Ipv6Addr::from(u128::from(u32::from(addr))))
+            }
+            _ => addr,
+        }
+    }
+
+    pub fn output(&self) -> Format {
+        self.output_format.or(self.format).unwrap_or_default()
+    }
+}
+
+pub struct InvalidNotation(pub String);
+
+impl Display for InvalidNotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid address or network: {}", self.0)
+    }
+}
+
+impl From<InvalidNotation> for (StatusCode, String) {
+    fn from(value: InvalidNotation) -> Self {
+        (StatusCode::BAD_REQUEST, value.to_string())
+    }
+}
+
+pub trait Notation: FromStr {
+    // `width` is the number of bits implied by the notation, if any.
+    fn from_bits(bits: u128, width: Option<u32>) -> Option<Self>;
+}
+
+// Without a width, integers up to u32::MAX are taken as IPv4 addresses.
+impl Notation for IpAddr {
+    fn from_bits(bits: u128, width: Option<u32>) -> Option<Self> {
+        match (width, u32::try_from(bits)) {
+            (Some(32) | None, Ok(bits)) => Some(IpAddr::V4(Ipv4Addr::from(bits))),
+            (Some(128) | None, _) => Some(IpAddr::V6(Ipv6Addr::from(bits))),
+            _ => None,
+        }
+    }
+}
+
+impl Notation for Ipv6Addr {
+    fn from_bits(bits: u128, width: Option<u32>) -> Option<Self> {
+        match width {
+            Some(128) | None => Some(Ipv6Addr::from(bits)),
+            _ => None,
+        }
+    }
+}
+
+impl Format {
+    // `width` only applies to integers, the other notations imply their own.
+    fn parse_as<A: Notation>(self, s: &str, width: Option<u32>) -> Result<A, InvalidNotation> {
+        let addr = match self {
+            Format::Text => s.parse().ok(),
+            Format::Integer => s.parse().ok().and_then(|bits| A::from_bits(bits, width)),
+            Format::Hex => Self::parse_digits(s, "0x", 16, [8, 32]),
+            Format::Binary => Self::parse_digits(s, "0b", 2, [32, 128]),
+        };
+
+        addr.ok_or_else(|| InvalidNotation(s.to_string()))
+    }
+
+    // Hex and binary strings are fixed-width, which tells the families apart.
+    fn parse_digits<A: Notation>(
+        s: &str,
+        prefix: &str,
+        radix: u32,
+        lengths: [usize; 2],
+    ) -> Option<A> {
+        let digits = s.strip_prefix(prefix).unwrap_or(s);
+        let width = match digits.len() {
+            len if len == lengths[0] => 32,
+            len if len == lengths[1] => 128,
+            _ => return None,
+        };
+
+        if !digits.chars().all(|c| c.is_digit(radix)) {
+            return None;
+        }
+
+        let bits = u128::from_str_radix(digits, radix).ok()?;
+        A::from_bits(bits, Some(width))
+    }
+
+    pub fn render(self, addr: IpAddr) -> String {
+        let bits = match addr {
+            IpAddr::V4(addr) => u128::from(u32::from(addr)),
+            IpAddr::V6(addr) => u128::from(addr),
+        };
+
+        match (self, addr) {
+            (Format::Text, _) => addr.to_string(),
+            (Format::Integer, _) => bits.to_string(),
+            (Format::Hex, IpAddr::V4(_)) => format!("{bits:08x}"),
+            (Format::Hex, IpAddr::V6(_)) => format!("{bits:032x}"),
+            (Format::Binary, IpAddr::V4(_)) => format!("{bits:032b}"),
+            (Format::Binary, IpAddr::V6(_)) => format!("{bits:0128b}"),
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use super::cipher::{AddressCipher, Algorithm};
+use crate::day23::parser;
+use axum::{
+    extract::Query,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use axum_extra::extract::Multipart;
+use serde::Deserialize;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+#[derive(Deserialize)]
+pub struct PcapParams {
+    key: Option<Ipv4Addr>,
+    v6_key: Option<Ipv6Addr>,
+    algo: Option<Algorithm>,
+}
+
+pub enum PcapError {
+    Truncated,
+    UnsupportedFormat,
+    UnsupportedLinkType,
+    MissingKey,
+}
+
+impl From<PcapError> for StatusCode {
+    fn from(value: PcapError) -> Self {
+        match value {
+            PcapError::Truncated | PcapError::MissingKey => StatusCode::BAD_REQUEST,
+            PcapError::UnsupportedFormat => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            PcapError::UnsupportedLinkType => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+}
+
+pub async fn anonymize(
+    Query(params): Query<PcapParams>,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, StatusCode> {
+    let capture = parser::parse_multipart_bytes(multipart, "pcap").await?;
+    let capture = rewrite_capture(capture.to_vec(), &params)?;
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/vnd.tcpdump.pcap")],
+        capture,
+    ))
+}
+
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: [u16; 2] = [0x8100, 0x88a8];
+
+const PROTOCOL_TCP: u8 = 6;
+const PROTOCOL_UDP: u8 = 17;
+const PROTOCOL_ICMPV6: u8 = 58;
+
+#[derive(Clone, Copy)]
+enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    fn detect(magic: [u8; 4]) -> Result<Self, PcapError> {
+        const MAGICS: [u32; 2] = [0xa1b2c3d4, 0xa1b23c4d];
+
+        if MAGICS.contains(&u32::from_le_bytes(magic)) {
+            Ok(Endian::Little)
+        } else if MAGICS.contains(&u32::from_be_bytes(magic)) {
+            Ok(Endian::Big)
+        } else {
+            Err(PcapError::UnsupportedFormat)
+        }
+    }
+
+    fn read_u32(self, bytes: &[u8]) -> u32 {
+        let bytes = bytes[..4].try_into().unwrap();
+        match self {
+            Endian::Little => u32::from_le_bytes(bytes),
+            Endian::Big => u32::from_be_bytes(bytes),
+        }
+    }
+}
+
+fn rewrite_capture(mut capture: Vec<u8>, params: &PcapParams) -> Result<Vec<u8>, PcapError> {
+    let header = capture.get(..24).ok_or(PcapError::Truncated)?;
+    let endian = Endian::detect(header[..4].try_into().unwrap())?;
+    let link_type = endian.read_u32(&header[20..24]) & 0xffff;
+
+    let locate: fn(&mut [u8]) -> Option<Packet<'_>> = match link_type {
+        LINKTYPE_ETHERNET => locate_ethernet,
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => locate_ip,
+        _ => return Err(PcapError::UnsupportedLinkType),
+    };
+
+    let mut offset = 24;
+    while offset < capture.len() {
+        let record = capture
+            .get(offset..offset + 16)
+            .ok_or(PcapError::Truncated)?;
+        let start = offset + 16;
+        let end = start + endian.read_u32(&record[8..12]) as usize;
+
+        let packet = capture.get_mut(start..end).ok_or(PcapError::Truncated)?;
+        match locate(packet) {
+            Some(Packet::V4(packet)) => {
+                let key = params.key.ok_or(PcapError::MissingKey)?;
+                rewrite_ipv4(packet, key, params.algo);
+            }
+            Some(Packet::V6(packet)) => {
+                let key = params.v6_key.ok_or(PcapError::MissingKey)?;
+                rewrite_ipv6(packet, key, params.algo);
+            }
+            None => {}
+        }
+        offset = end;
+    }
+
+    Ok(capture)
+}
+
+enum Packet<'a> {
+    V4(&'a mut [u8]),
+    V6(&'a mut [u8]),
+}
+
+// Packets are rewritten as far as they were captured; anything cut off by the
+// snapshot length is left as it is. A key is only needed once a packet of its
+// family shows up.
+fn locate_ethernet(frame: &mut [u8]) -> Option<Packet<'_>> {
+    let mut offset = 12;
+    let ethertype = loop {
+        let ethertype = read_u16(frame.get(offset..offset + 2)?);
+        if ETHERTYPE_VLAN.contains(&ethertype) {
+            offset += 4;
+        } else {
+            break ethertype;
+        }
+    };
+
+    let packet = frame.get_mut(offset + 2..)?;
+    match ethertype {
+        ETHERTYPE_IPV4 => Some(Packet::V4(packet)),
+        ETHERTYPE_IPV6 => Some(Packet::V6(packet)),
+        _ => None,
+    }
+}
+
+fn locate_ip(packet: &mut [u8]) -> Option<Packet<'_>> {
+    match packet.first()? >> 4 {
+        4 => Some(Packet::V4(packet)),
+        6 => Some(Packet::V6(packet)),
+        _ => None,
+    }
+}
+
+fn rewrite_ipv4(packet: &mut [u8], key: Ipv4Addr, algo: Option<Algorithm>) -> Option<()> {
+    let header_len = usize::from(packet.first()? & 0x0f) * 4;
+    if header_len < 20 || packet.len() < header_len {
+        return None;
+    }
+
+    let algo = algo.unwrap_or(Algorithm::Add);
+    let old = packet[12..20].to_vec();
+    for range in [12..16, 16..20] {
+        let octets: [u8; 4] = packet[range.clone()].try_into().unwrap();
+        let addr = algo.encrypt(Ipv4Addr::from(octets), key);
+        packet[range].copy_from_slice(&addr.octets());
+    }
+    let new = packet[12..20].to_vec();
+
+    packet[10..12].fill(0);
+    let checksum = !ones_complement_sum(&packet[..header_len]);
+    packet[10..12].copy_from_slice(&checksum.to_be_bytes());
+
+    let fragment_offset = read_u16(&packet[6..8]) & 0x1fff;
+    if fragment_offset != 0 {
+        return None;
+    }
+
+    let protocol = packet[9];
+    rewrite_transport(&mut packet[header_len..], protocol, &old, &new, true)
+}
+
+fn rewrite_ipv6(packet: &mut [u8], key: Ipv6Addr, algo: Option<Algorithm>) -> Option<()> {
+    if packet.len() < 40 {
+        return None;
+    }
+
+    let algo = algo.unwrap_or(Algorithm::Xor);
+    let old = packet[8..40].to_vec();
+    for range in [8..24, 24..40] {
+        let octets: [u8; 16] = packet[range.clone()].try_into().unwrap();
+        let addr = algo.encrypt(Ipv6Addr::from(octets), key);
+        packet[range].copy_from_slice(&addr.octets());
+    }
+    let new = packet[8..40].to_vec();
+
+    let mut next_header = packet[6];
+    let mut offset = 40;
+    loop {
+        match next_header {
+            // Hop-by-hop options, routing and destination options headers.
+            0 | 43 | 60 => {
+                let header = packet.get(offset..offset + 2)?;
+                next_header = header[0];
+                offset += (usize::from(header[1]) + 1) * 8;
+            }
+            // Fragment header.
+            44 => {
+                let header = packet.get(offset..offset + 8)?;
+                if read_u16(&header[2..4]) >> 3 != 0 {
+                    return None;
+                }
+                next_header = header[0];
+                offset += 8;
+            }
+            // Authentication header.
+            51 => {
+                let header = packet.get(offset..offset + 2)?;
+                next_header = header[0];
+                offset += (usize::from(header[1]) + 2) * 4;
+            }
+            _ => break,
+        }
+    }
+
+    rewrite_transport(packet.get_mut(offset..)?, next_header, &old, &new, false)
+}
+
+// The pseudo header only changes in its addresses, so the checksum is updated
+// incrementally (RFC 1624), which also works for truncated segments.
+fn rewrite_transport(
+    segment: &mut [u8],
+    protocol: u8,
+    old: &[u8],
+    new: &[u8],
+    is_ipv4: bool,
+) -> Option<()> {
+    let position = match protocol {
+        PROTOCOL_TCP => 16,
+        PROTOCOL_UDP => 6,
+        PROTOCOL_ICMPV6 if !is_ipv4 => 2,
+        _ => return None,
+    };
+
+    let field = segment.get_mut(position..position + 2)?;
+    let checksum = read_u16(field);
+    if protocol == PROTOCOL_UDP && is_ipv4 && checksum == 0 {
+        return None;
+    }
+
+    let sum = u32::from(!checksum)
+        + u32::from(!ones_complement_sum(old))
+        + u32::from(ones_complement_sum(new));
+    let mut checksum = !fold(sum);
+    if protocol == PROTOCOL_UDP && checksum == 0 {
+        checksum = 0xffff;
+    }
+
+    field.copy_from_slice(&checksum.to_be_bytes());
+    Some(())
+}
+
+fn ones_complement_sum(bytes: &[u8]) -> u16 {
+    let sum = bytes
+        .chunks(2)
+        .map(|word| u32::from(word[0]) << 8 | u32::from(*word.get(1).unwrap_or(&0)))
+        .sum();
+    fold(sum)
+}
+
+fn fold(mut sum: u32) -> u16 {
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    sum as u16
+}
+
+fn read_u16(bytes: &[u8]) -> u16 {
+    u16::from_be_bytes([bytes[0], bytes[1]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An IPv4 header and a UDP datagram, both with valid checksums.
+    fn udp_packet() -> Vec<u8> {
+        let mut packet = vec![
+            0x45,
+            0,
+            0,
+            32,
+            0,
+            0,
+            0,
+            0,
+            64,
+            PROTOCOL_UDP,
+            0,
+            0,
+            10,
+            0,
+            0,
+            1,
+            192,
+            168,
+            1,
+            2,
+        ];
+        let checksum = !ones_complement_sum(&packet);
+        packet[10..12].copy_from_slice(&checksum.to_be_bytes());
+
+        let mut datagram = vec![0x12, 0x34, 0x00, 0x35, 0, 12, 0, 0, b'm', b'i', b'l', b'k'];
+        let checksum = !ones_complement_sum(&udp_checksum_input(&packet, &datagram));
+        datagram[6..8].copy_from_slice(&checksum.to_be_bytes());
+
+        packet.extend(datagram);
+        packet
+    }
+
+    fn udp_checksum_input(header: &[u8], datagram: &[u8]) -> Vec<u8> {
+        let mut input = header[12..20].to_vec();
+        input.extend([0, PROTOCOL_UDP]);
+        input.extend((datagram.len() as u16).to_be_bytes());
+        input.extend(datagram);
+        input
+    }
+
+    #[test]
+    fn rewritten_checksums_verify() {
+        for algo in [Algorithm::Add, Algorithm::Xor, Algorithm::Feistel] {
+            let mut packet = udp_packet();
+            rewrite_ipv4(&mut packet, "1.2.3.4".parse().unwrap(), Some(algo)).unwrap();
+
+            let (header, datagram) = packet.split_at(20);
+            assert_ne!(&header[12..20], &udp_packet()[12..20]);
+            assert_eq!(ones_complement_sum(header), 0xffff);
+            assert_eq!(
+                ones_complement_sum(&udp_checksum_input(header, datagram)),
+                0xffff
+            );
+        }
+    }
+
+    #[test]
+    fn incremental_update_matches_recomputation() {
+        let mut packet = udp_packet();
+        rewrite_ipv4(&mut packet, "200.100.50.25".parse().unwrap(), None).unwrap();
+
+        let (header, datagram) = packet.split_at(20);
+        let mut zeroed = datagram.to_vec();
+        zeroed[6..8].fill(0);
+        let recomputed = !ones_complement_sum(&udp_checksum_input(header, &zeroed));
+        assert_eq!(read_u16(&datagram[6..8]), recomputed);
+    }
+}
@@ -0,0 +1,190 @@
+use super::{
+    cipher::{Address, AddressCipher, Algorithm},
+    notation::{Format, Formats, InvalidNotation, Notation},
+};
+use axum::{
+    body::Body,
+    http::{HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use futures::stream;
+use serde::{de, Deserialize, Deserializer};
+use std::{convert::Infallible, fmt::Display, iter, net::IpAddr};
+
+pub enum Subject<A> {
+    Host(A),
+    Network { addr: A, prefix: u32 },
+}
+
+impl<A: Notation> Subject<A> {
+    pub fn parse(s: &str, formats: &Formats) -> Result<Self, InvalidNotation> {
+        match s.split_once('/') {
+            Some((addr, prefix)) => Ok(Subject::Network {
+                addr: formats.parse(addr)?,
+                prefix: prefix.parse().map_err(|_| InvalidNotation(s.to_string()))?,
+            }),
+            None => formats.parse(s).map(Subject::Host),
+        }
+    }
+}
+
+// The query string deserializer has no support for u128.
+pub fn parse_index<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u128>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|index| index.parse().map_err(de::Error::custom))
+        .transpose()
+}
+
+pub enum SubnetError {
+    InvalidPrefix(u32),
+    ScatteredNetwork(u32),
+}
+
+impl Display for SubnetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubnetError::InvalidPrefix(prefix) => {
+                write!(f, "prefix length /{prefix} is out of range")
+            }
+            SubnetError::ScatteredNetwork(prefix) => {
+                write!(
+                    f,
+                    "algo and key don't map a /{prefix} network onto one network"
+                )
+            }
+        }
+    }
+}
+
+impl From<SubnetError> for (StatusCode, String) {
+    fn from(value: SubnetError) -> Self {
+        (StatusCode::BAD_REQUEST, value.to_string())
+    }
+}
+
+pub struct Page {
+    offset: u128,
+    limit: u128,
+}
+
+impl Page {
+    const MAX_HOSTS: u128 = 4096;
+
+    pub fn new(offset: Option<u128>, limit: Option<u128>) -> Self {
+        Self {
+            offset: offset.unwrap_or(0),
+            limit: limit.unwrap_or(Self::MAX_HOSTS).min(Self::MAX_HOSTS),
+        }
+    }
+}
+
+pub struct Network<A> {
+    addr: A,
+    prefix: u32,
+}
+
+impl<A: Address + Send + 'static> Network<A> {
+    pub fn new(addr: A, prefix: u32) -> Result<Self, SubnetError> {
+        if prefix > A::BITS {
+            return Err(SubnetError::InvalidPrefix(prefix));
+        }
+
+        let addr = Self::mask(addr, prefix);
+        Ok(Self { addr, prefix })
+    }
+
+    // Prefixes of IPv4-mapped networks count the 96 bits of the ::ffff: part.
+    pub fn from_mapped(addr: A, prefix: u32, mapped: bool) -> Result<Self, SubnetError> {
+        let offset = if mapped { 128 - A::BITS } else { 0 };
+        let prefix = prefix
+            .checked_sub(offset)
+            .ok_or(SubnetError::InvalidPrefix(prefix))?;
+        Self::new(addr, prefix)
+    }
+
+    fn host_mask(prefix: u32) -> u128 {
+        let host_bits = A::BITS - prefix;
+        u128::MAX.checked_shr(128 - host_bits).unwrap_or(0)
+    }
+
+    fn mask(addr: A, prefix: u32) -> A {
+        A::from_bits(addr.to_bits() & !Self::host_mask(prefix))
+    }
+
+    fn last_index(&self) -> u128 {
+        Self::host_mask(self.prefix)
+    }
+
+    fn hosts(&self, page: &Page) -> impl Iterator<Item = A> + Send + 'static {
+        let base = self.addr.to_bits();
+        let count = usize::try_from(page.limit).unwrap();
+
+        (page.offset..=self.last_index())
+            .take(count)
+            .map(move |index| A::from_bits(base | index))
+    }
+
+    fn next_offset(&self, page: &Page) -> Option<u128> {
+        page.offset
+            .checked_add(page.limit)
+            .filter(|&next| next <= self.last_index())
+    }
+
+    // Xor only ever flips the same bits, and lane-wise adds only carry within
+    // the lane the prefix ends in, unless the key has no host bits there. The
+    // other algorithms scatter hosts, except for the trivial prefixes.
+    fn keeps_prefix(&self, key: A, algo: Algorithm) -> bool {
+        if self.prefix == 0 || self.prefix == A::BITS {
+            return true;
+        }
+
+        match algo {
+            Algorithm::Xor => true,
+            Algorithm::Add => {
+                let lane_bits = A::BITS / key.lanes().len() as u32;
+                let lane_low = (A::BITS - self.prefix) / lane_bits * lane_bits;
+                let carried = Self::host_mask(self.prefix) & !(u128::MAX << lane_low);
+                key.to_bits() & (Self::host_mask(self.prefix) ^ carried) == 0
+            }
+            Algorithm::Rotate | Algorithm::Feistel => false,
+        }
+    }
+
+    pub fn encrypt(
+        self,
+        key: A,
+        algo: Algorithm,
+        page: Page,
+        render: impl Fn(A) -> IpAddr + Send + 'static,
+        format: Format,
+    ) -> Result<Response, SubnetError> {
+        if !self.keeps_prefix(key, algo) {
+            return Err(SubnetError::ScatteredNetwork(self.prefix));
+        }
+
+        let dest = render(Self::mask(algo.encrypt(self.addr, key), self.prefix));
+        let prefix = match dest {
+            IpAddr::V4(_) => self.prefix,
+            IpAddr::V6(_) => self.prefix + 128 - A::BITS,
+        };
+
+        let mappings = self.hosts(&page).map(move |host| {
+            let dest = algo.encrypt(host, key);
+            let host = format.render(render(host));
+            let dest = format.render(render(dest));
+            format!("{host} -> {dest}")
+        });
+        let lines = iter::once(format!("{}/{prefix}", format.render(dest)))
+            .chain(mappings)
+            .map(|line| Ok::<_, Infallible>(line + "\n"));
+
+        let mut response = Body::from_stream(stream::iter(lines)).into_response();
+        if let Some(next_offset) = self.next_offset(&page) {
+            response.headers_mut().insert(
+                "X-Next-Offset",
+                HeaderValue::from_str(&next_offset.to_string()).unwrap(),
+            );
+        }
+        Ok(response)
+    }
+}
@@ -1,5 +1,5 @@
 mod domain;
-mod parser;
+pub mod parser;
 
 use axum::{extract::Path, http::StatusCode, response::Html};
 use axum_extra::extract::Multipart;
@@ -1,4 +1,4 @@
-use axum::http::StatusCode;
+use axum::{body::Bytes, http::StatusCode};
 use axum_extra::extract::Multipart;
 use hex::FromHexError;
 use toml::{Table, Value};
@@ -8,14 +8,22 @@ pub enum ParseError {
     Checksum,
 }
 
-pub async fn parse_multipart(mut multipart: Multipart, name: &str) -> Result<String, StatusCode> {
+pub async fn parse_multipart(multipart: Multipart, name: &str) -> Result<String, StatusCode> {
+    let bytes = parse_multipart_bytes(multipart, name).await?;
+    String::from_utf8(bytes.to_vec()).map_err(|_| StatusCode::BAD_REQUEST)
+}
+
+pub async fn parse_multipart_bytes(
+    mut multipart: Multipart,
+    name: &str,
+) -> Result<Bytes, StatusCode> {
     loop {
         match multipart.next_field().await {
             Ok(Some(field)) => {
                 if let Some(field_name) = field.name() {
                     if field_name == name {
-                        if let Ok(text) = field.text().await {
-                            return Ok(text);
+                        if let Ok(bytes) = field.bytes().await {
+                            return Ok(bytes);
                         } else {
                             return Err(StatusCode::BAD_REQUEST);
                         }
@@ -27,6 +35,37 @@ pub async fn parse_multipart(mut multipart: Multipart, name: &str) -> Result<Str
     }
 }
 
+pub struct Upload {
+    pub name: Option<String>,
+    pub file_name: Option<String>,
+    pub content_type: Option<String>,
+    pub bytes: Bytes,
+}
+
+pub async fn parse_multipart_files(mut multipart: Multipart) -> Result<Vec<Upload>, StatusCode> {
+    let mut uploads = vec![];
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|_| StatusCode::BAD_REQUEST)?
+    {
+        let name = field.name().map(str::to_string);
+        let file_name = field.file_name().map(str::to_string);
+        let content_type = field.content_type().map(str::to_string);
+        let bytes = field.bytes().await.map_err(|_| StatusCode::BAD_REQUEST)?;
+
+        uploads.push(Upload {
+            name,
+            file_name,
+            content_type,
+            bytes,
+        });
+    }
+
+    Ok(uploads)
+}
+
 pub fn parse_lock_file(lock_file: &str) -> Result<Vec<Value>, ParseError> {
     let mut table = lock_file
         .parse::<Table>()
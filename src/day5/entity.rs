@@ -0,0 +1,99 @@
+use serde::Serialize;
+use sqlx::{
+    prelude::FromRow,
+    types::chrono::{DateTime, Utc},
+};
+use std::fmt::Display;
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct Order {
+    pub item: String,
+    pub quantity: u32,
+    pub source: Source,
+}
+
+impl Display for Order {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}: {}", self.item, self.quantity))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, strum_macros::Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Source {
+    Package,
+    Workspace,
+}
+
+#[derive(Serialize)]
+pub struct OrderReport {
+    pub index: usize,
+    pub source: Source,
+    #[serde(flatten)]
+    pub verdict: Verdict,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Verdict {
+    Accepted {
+        item: String,
+        quantity: u32,
+    },
+    Rejected {
+        #[serde(flatten)]
+        rejection: Rejection,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum Rejection {
+    NotATable,
+    MissingField { field: &'static str },
+    WrongType { field: &'static str },
+    Negative,
+    Overflow,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct Submission {
+    pub id: Uuid,
+    pub package: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub order_count: i64,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct StoredOrder {
+    pub item: String,
+    pub quantity: i64,
+    pub source: String,
+}
+
+#[derive(Serialize)]
+pub struct SubmissionDetail {
+    #[serde(flatten)]
+    pub submission: Submission,
+    pub orders: Vec<StoredOrder>,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct ItemTotal {
+    pub item: String,
+    pub quantity: i64,
+}
+
+#[derive(Serialize)]
+pub struct FileResult {
+    pub file: String,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submission_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orders: Option<Vec<Order>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
@@ -0,0 +1,163 @@
+use super::entity::{ItemTotal, Order, StoredOrder, Submission, SubmissionDetail};
+use crate::paging;
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use itertools::Itertools;
+use serde::Deserialize;
+use sqlx::{
+    types::chrono::{DateTime, Utc},
+    PgPool,
+};
+use std::{ops::Deref, sync::Arc};
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct ListParams {
+    limit: Option<i64>,
+    #[serde(default)]
+    offset: i64,
+}
+
+#[derive(Deserialize)]
+pub struct TotalsParams {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+}
+
+pub struct StoreError(sqlx::Error);
+
+impl From<sqlx::Error> for StoreError {
+    fn from(value: sqlx::Error) -> Self {
+        StoreError(value)
+    }
+}
+
+impl From<StoreError> for (StatusCode, String) {
+    fn from(value: StoreError) -> Self {
+        tracing::error!("failed to store manifest submission: {}", value.0);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to store manifest".to_string(),
+        )
+    }
+}
+
+// The orders go in with a single statement, however many there are.
+pub async fn store(
+    pool: &PgPool,
+    package: Option<String>,
+    orders: &[Order],
+) -> Result<Uuid, StoreError> {
+    let id = Uuid::new_v4();
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query("INSERT INTO manifest_submissions (id, package) VALUES ($1, $2)")
+        .bind(id)
+        .bind(package)
+        .execute(&mut *transaction)
+        .await?;
+
+    sqlx::query(
+        r#"INSERT INTO manifest_orders (submission_id, position, item, quantity, source)
+        SELECT $1, * FROM UNNEST($2::INT[], $3::TEXT[], $4::BIGINT[], $5::TEXT[])"#,
+    )
+    .bind(id)
+    .bind((0..orders.len() as i32).collect_vec())
+    .bind(orders.iter().map(|order| order.item.clone()).collect_vec())
+    .bind(
+        orders
+            .iter()
+            .map(|order| i64::from(order.quantity))
+            .collect_vec(),
+    )
+    .bind(
+        orders
+            .iter()
+            .map(|order| order.source.to_string())
+            .collect_vec(),
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+    Ok(id)
+}
+
+pub async fn list(
+    State(pool): State<Arc<PgPool>>,
+    Query(params): Query<ListParams>,
+) -> Result<Json<Vec<Submission>>, StatusCode> {
+    let limit = paging::limit(params.limit, params.offset)?;
+
+    let submissions = sqlx::query_as::<_, Submission>(
+        r#"SELECT s.id, s.package, s.created_at, COUNT(o.position) AS order_count
+        FROM manifest_submissions s
+        LEFT JOIN manifest_orders o ON o.submission_id = s.id
+        GROUP BY s.id
+        ORDER BY s.created_at DESC, s.id
+        LIMIT $1 OFFSET $2"#,
+    )
+    .bind(limit)
+    .bind(params.offset)
+    .fetch_all(pool.deref())
+    .await
+    .unwrap();
+
+    Ok(Json(submissions))
+}
+
+pub async fn get(
+    State(pool): State<Arc<PgPool>>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<SubmissionDetail>, StatusCode> {
+    let submission = sqlx::query_as::<_, Submission>(
+        r#"SELECT s.id, s.package, s.created_at, COUNT(o.position) AS order_count
+        FROM manifest_submissions s
+        LEFT JOIN manifest_orders o ON o.submission_id = s.id
+        WHERE s.id = $1
+        GROUP BY s.id"#,
+    )
+    .bind(id)
+    .fetch_optional(pool.deref())
+    .await
+    .unwrap()
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let orders = sqlx::query_as::<_, StoredOrder>(
+        r#"SELECT item, quantity, source FROM manifest_orders
+        WHERE submission_id = $1
+        ORDER BY position"#,
+    )
+    .bind(id)
+    .fetch_all(pool.deref())
+    .await
+    .unwrap();
+
+    Ok(Json(SubmissionDetail { submission, orders }))
+}
+
+// Both ends of the range are optional; `from` is inclusive and `to` exclusive.
+pub async fn totals(
+    State(pool): State<Arc<PgPool>>,
+    Query(params): Query<TotalsParams>,
+) -> Json<Vec<ItemTotal>> {
+    let totals = sqlx::query_as::<_, ItemTotal>(
+        r#"SELECT o.item, SUM(o.quantity)::BIGINT AS quantity
+        FROM manifest_orders o
+        JOIN manifest_submissions s ON s.id = o.submission_id
+        WHERE ($1::TIMESTAMPTZ IS NULL OR s.created_at >= $1)
+          AND ($2::TIMESTAMPTZ IS NULL OR s.created_at < $2)
+        GROUP BY o.item
+        ORDER BY o.item"#,
+    )
+    .bind(params.from)
+    .bind(params.to)
+    .fetch_all(pool.deref())
+    .await
+    .unwrap();
+
+    Json(totals)
+}
@@ -0,0 +1,162 @@
+use cargo_manifest::{Dependency, DependencyDetail, DepsSet, Manifest};
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyKind {
+    Normal,
+    Dev,
+    Build,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DependencySource {
+    Registry {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        registry: Option<String>,
+    },
+    Git {
+        url: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        branch: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tag: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        rev: Option<String>,
+    },
+    Path {
+        path: String,
+    },
+    // Inherited from a workspace that isn't part of the manifest.
+    Workspace,
+}
+
+#[derive(Serialize)]
+pub struct DependencyEntry {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    package: Option<String>,
+    kind: DependencyKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target: Option<String>,
+    version_req: Option<String>,
+    source: DependencySource,
+    features: Vec<String>,
+    default_features: bool,
+    optional: bool,
+}
+
+pub fn inventory(manifest: &Manifest) -> Vec<DependencyEntry> {
+    let workspace = manifest
+        .workspace
+        .as_ref()
+        .and_then(|workspace| workspace.dependencies.as_ref());
+
+    let mut tables = vec![];
+    for (dependencies, kind) in [
+        (&manifest.dependencies, DependencyKind::Normal),
+        (&manifest.dev_dependencies, DependencyKind::Dev),
+        (&manifest.build_dependencies, DependencyKind::Build),
+    ] {
+        if let Some(dependencies) = dependencies {
+            tables.push((dependencies, kind, None));
+        }
+    }
+    for (target, deps) in manifest.target.iter().flatten() {
+        tables.extend([
+            (&deps.dependencies, DependencyKind::Normal, Some(target)),
+            (&deps.dev_dependencies, DependencyKind::Dev, Some(target)),
+            (
+                &deps.build_dependencies,
+                DependencyKind::Build,
+                Some(target),
+            ),
+        ]);
+    }
+
+    tables
+        .into_iter()
+        .flat_map(|(dependencies, kind, target)| {
+            dependencies.iter().map(move |(name, dependency)| {
+                entry(name, dependency, kind, target.cloned(), workspace)
+            })
+        })
+        .collect()
+}
+
+// Inherited dependencies take their version and source from the workspace,
+// and add their own features to the workspace ones.
+fn entry(
+    name: &str,
+    dependency: &Dependency,
+    kind: DependencyKind,
+    target: Option<String>,
+    workspace: Option<&DepsSet>,
+) -> DependencyEntry {
+    let (detail, extra_features, optional) = match dependency {
+        Dependency::Simple(version) => (
+            Some(DependencyDetail {
+                version: Some(version.clone()),
+                ..Default::default()
+            }),
+            vec![],
+            None,
+        ),
+        Dependency::Detailed(detail) => (Some(detail.clone()), vec![], None),
+        Dependency::Inherited(inherited) => {
+            let detail = workspace
+                .and_then(|workspace| workspace.get(name))
+                .map(|dependency| match dependency {
+                    Dependency::Simple(version) => DependencyDetail {
+                        version: Some(version.clone()),
+                        ..Default::default()
+                    },
+                    Dependency::Detailed(detail) => detail.clone(),
+                    Dependency::Inherited(_) => DependencyDetail::default(),
+                });
+            let features = inherited.features.clone().unwrap_or_default();
+            (detail, features, inherited.optional)
+        }
+    };
+
+    let source = match &detail {
+        Some(DependencyDetail {
+            git: Some(url),
+            branch,
+            tag,
+            rev,
+            ..
+        }) => DependencySource::Git {
+            url: url.clone(),
+            branch: branch.clone(),
+            tag: tag.clone(),
+            rev: rev.clone(),
+        },
+        Some(DependencyDetail {
+            path: Some(path), ..
+        }) => DependencySource::Path { path: path.clone() },
+        Some(DependencyDetail { registry, .. }) => DependencySource::Registry {
+            registry: registry.clone(),
+        },
+        None => DependencySource::Workspace,
+    };
+
+    let detail = detail.unwrap_or_default();
+    let mut features = detail.features.unwrap_or_default();
+    features.extend(extra_features);
+    features.sort();
+    features.dedup();
+
+    DependencyEntry {
+        name: name.to_string(),
+        package: detail.package,
+        kind,
+        target,
+        version_req: detail.version,
+        source,
+        features,
+        default_features: detail.default_features.unwrap_or(true),
+        optional: optional.or(detail.optional).unwrap_or(false),
+    }
+}
@@ -0,0 +1,228 @@
+use cargo_manifest::Manifest;
+use semver::{Version, VersionReq};
+use serde::Serialize;
+use toml::{Table, Value};
+
+const KNOWN_KEYS: [&str; 21] = [
+    "cargo-features",
+    "package",
+    "project",
+    "lib",
+    "bin",
+    "example",
+    "test",
+    "bench",
+    "dependencies",
+    "dev-dependencies",
+    "dev_dependencies",
+    "build-dependencies",
+    "build_dependencies",
+    "target",
+    "features",
+    "patch",
+    "replace",
+    "profile",
+    "workspace",
+    "badges",
+    "lints",
+];
+
+const KNOWN_EDITIONS: [&str; 4] = ["2015", "2018", "2021", "2024"];
+
+const DEPENDENCY_TABLES: [&str; 5] = [
+    "dependencies",
+    "dev-dependencies",
+    "dev_dependencies",
+    "build-dependencies",
+    "build_dependencies",
+];
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Serialize)]
+pub struct Finding {
+    rule: &'static str,
+    severity: Severity,
+    path: String,
+    message: String,
+}
+
+impl Finding {
+    fn error(rule: &'static str, path: String, message: String) -> Self {
+        Finding {
+            rule,
+            severity: Severity::Error,
+            path,
+            message,
+        }
+    }
+
+    fn warning(rule: &'static str, path: String, message: String) -> Self {
+        Finding {
+            rule,
+            severity: Severity::Warning,
+            path,
+            message,
+        }
+    }
+}
+
+// The manifest is linted as a plain table, so a manifest Cargo would reject
+// still gets every other finding reported.
+pub fn lint(manifest: Table) -> Vec<Finding> {
+    let mut findings = vec![];
+
+    if let Err(error) = Value::Table(manifest.clone()).try_into::<Manifest>() {
+        findings.push(Finding::error(
+            "invalid-manifest",
+            String::new(),
+            error.to_string().trim_end().to_string(),
+        ));
+    }
+
+    for key in manifest.keys() {
+        if !KNOWN_KEYS.contains(&key.as_str()) {
+            findings.push(Finding::warning(
+                "unknown-key",
+                key.clone(),
+                format!("unknown top-level key `{key}`"),
+            ));
+        }
+    }
+
+    let package = manifest.get("package").and_then(Value::as_table);
+    let workspace_package = manifest
+        .get("workspace")
+        .and_then(|workspace| workspace.get("package"))
+        .and_then(Value::as_table);
+    for (package, path) in [
+        (package, "package"),
+        (workspace_package, "workspace.package"),
+    ] {
+        if let Some(package) = package {
+            lint_package(package, path, &mut findings);
+        }
+    }
+
+    let mut scopes = vec![(&manifest, String::new())];
+    if let Some(targets) = manifest.get("target").and_then(Value::as_table) {
+        scopes.extend(
+            targets.iter().filter_map(|(target, table)| {
+                Some((table.as_table()?, format!("target.{target}.")))
+            }),
+        );
+    }
+    for (scope, prefix) in scopes {
+        lint_dependencies(scope, &prefix, &mut findings);
+    }
+
+    if let Some(dependencies) = manifest
+        .get("workspace")
+        .and_then(|workspace| workspace.get("dependencies"))
+        .and_then(Value::as_table)
+    {
+        lint_requirements(dependencies, "workspace.dependencies", &mut findings);
+    }
+
+    findings
+}
+
+// Inherited fields are tables and get linted where the workspace defines them.
+fn lint_package(package: &Table, path: &str, findings: &mut Vec<Finding>) {
+    if let Some(Value::String(version)) = package.get("version") {
+        if let Err(error) = Version::parse(version) {
+            findings.push(Finding::error(
+                "invalid-version",
+                format!("{path}.version"),
+                format!("`{version}` is not a semver version: {error}"),
+            ));
+        }
+    }
+
+    if let Some(Value::String(license)) = package.get("license") {
+        if let Err(error) = spdx::Expression::parse(license) {
+            findings.push(Finding::error(
+                "invalid-license",
+                format!("{path}.license"),
+                format!("`{license}` is not an SPDX license expression: {error}"),
+            ));
+        }
+    }
+
+    if let Some(Value::String(edition)) = package.get("edition") {
+        if !KNOWN_EDITIONS.contains(&edition.as_str()) {
+            findings.push(Finding::error(
+                "unknown-edition",
+                format!("{path}.edition"),
+                format!("`{edition}` is not a known edition"),
+            ));
+        }
+    }
+}
+
+fn lint_dependencies(scope: &Table, prefix: &str, findings: &mut Vec<Finding>) {
+    let tables = DEPENDENCY_TABLES.iter().filter_map(|name| {
+        let table = scope.get(*name)?.as_table()?;
+        Some((table, format!("{prefix}{name}")))
+    });
+
+    for (table, path) in tables {
+        lint_requirements(table, &path, findings);
+    }
+
+    let dependencies = ["dependencies"]
+        .iter()
+        .filter_map(|name| scope.get(*name)?.as_table())
+        .flat_map(|table| table.keys());
+    let dev_dependencies = ["dev-dependencies", "dev_dependencies"]
+        .iter()
+        .filter_map(|name| scope.get(*name)?.as_table())
+        .flat_map(|table| table.keys())
+        .collect::<Vec<_>>();
+
+    for name in dependencies.filter(|name| dev_dependencies.contains(name)) {
+        findings.push(Finding::warning(
+            "duplicate-dependency",
+            format!("{prefix}dev-dependencies.{name}"),
+            format!("`{name}` is listed in both dependencies and dev-dependencies"),
+        ));
+    }
+}
+
+fn lint_requirements(table: &Table, path: &str, findings: &mut Vec<Finding>) {
+    for (name, dependency) in table {
+        let requirement = match dependency {
+            Value::String(requirement) => requirement,
+            Value::Table(detail) => match detail.get("version") {
+                Some(Value::String(requirement)) => requirement,
+                _ => continue,
+            },
+            _ => continue,
+        };
+
+        match VersionReq::parse(requirement) {
+            Ok(req) if is_wildcard(&req) => findings.push(Finding::warning(
+                "wildcard-requirement",
+                format!("{path}.{name}"),
+                format!("`{name}` has the wildcard version requirement `{requirement}`"),
+            )),
+            Ok(_) => (),
+            Err(error) => findings.push(Finding::error(
+                "invalid-requirement",
+                format!("{path}.{name}"),
+                format!("`{requirement}` is not a version requirement: {error}"),
+            )),
+        }
+    }
+}
+
+// `*` parses into a requirement without any comparators. Partial wildcards
+// like `1.*` still pin a major version, so they're fine.
+fn is_wildcard(req: &VersionReq) -> bool {
+    req.comparators.is_empty()
+}
@@ -0,0 +1,405 @@
+mod entity;
+pub mod history;
+mod inventory;
+mod linter;
+mod output;
+mod rules;
+mod scheme;
+
+use crate::{
+    admin,
+    day23::parser::{self, Upload},
+    media_type::{ContentType, MediaType},
+};
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use axum_extra::extract::Multipart;
+use cargo_manifest::{Manifest, MaybeInherited, Package, WorkspacePackage};
+use entity::{FileResult, Order, OrderReport, Rejection, Source, Verdict};
+use output::OutputFormat;
+use rules::{Fields, Rules};
+pub use scheme::create_tables;
+use serde::{de::DeserializeOwned, Deserialize};
+use sqlx::PgPool;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+use toml::{Table, Value};
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct ManifestParams {
+    #[serde(default)]
+    validate: bool,
+    #[serde(default)]
+    aggregate: bool,
+    sort: Option<SortKey>,
+    #[serde(default)]
+    desc: bool,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum SortKey {
+    Item,
+    Quantity,
+}
+
+pub struct ManifestState {
+    pub pool: PgPool,
+    rules: RwLock<Rules>,
+    admin_secret: Option<String>,
+}
+
+// Without an admin secret, the rules stay as they were loaded at startup.
+pub fn create_manifest_state(pool: PgPool, admin_secret: Option<String>) -> ManifestState {
+    ManifestState {
+        pool,
+        rules: RwLock::new(Rules::load().unwrap()),
+        admin_secret,
+    }
+}
+
+pub async fn reload_rules(
+    State(state): State<Arc<ManifestState>>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    admin::authorize(&headers, state.admin_secret.as_deref(), "rules reload")?;
+
+    let reloaded = Rules::load()?;
+    *state.rules.write().unwrap() = reloaded;
+    Ok(StatusCode::OK)
+}
+
+pub async fn manifest(
+    State(state): State<Arc<ManifestState>>,
+    Query(params): Query<ManifestParams>,
+    content_type: ContentType,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Response, (StatusCode, String)> {
+    let manifest: Manifest = parse(content_type, &body)?;
+
+    state.rules.read().unwrap().check(&fields(&manifest))?;
+
+    let package = package_name(&manifest);
+    let is_workspace = manifest.workspace.is_some();
+    let sources = sources(manifest);
+
+    if params.validate {
+        return Ok(Json(report(sources)).into_response());
+    }
+
+    let mut orders = collect_orders(sources);
+    let id = history::store(&state.pool, package, &orders).await?;
+
+    if params.aggregate {
+        orders = aggregate(orders);
+    }
+    if let Some(key) = params.sort {
+        sort(&mut orders, key, params.desc);
+    }
+
+    let mut response = if orders.is_empty() {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        OutputFormat::negotiate(&headers).render(&orders, is_workspace)
+    };
+    response
+        .headers_mut()
+        .insert("X-Submission-Id", id.to_string().parse().unwrap());
+    Ok(response)
+}
+
+// Every part of the upload is processed as a manifest of its own, so one bad
+// file doesn't fail the others.
+pub async fn manifests(
+    State(state): State<Arc<ManifestState>>,
+    multipart: Multipart,
+) -> Result<Json<Vec<FileResult>>, StatusCode> {
+    let uploads = parser::parse_multipart_files(multipart).await?;
+
+    let mut results = vec![];
+    for (index, upload) in uploads.into_iter().enumerate() {
+        let file = upload
+            .file_name
+            .clone()
+            .or_else(|| upload.name.clone())
+            .unwrap_or_else(|| format!("#{index}"));
+
+        let result = match process_upload(&state, upload).await {
+            Ok((id, orders)) => FileResult {
+                file,
+                status: if orders.is_empty() {
+                    StatusCode::NO_CONTENT.as_u16()
+                } else {
+                    StatusCode::OK.as_u16()
+                },
+                submission_id: Some(id),
+                orders: Some(orders),
+                error: None,
+            },
+            Err((status, error)) => FileResult {
+                file,
+                status: status.as_u16(),
+                submission_id: None,
+                orders: None,
+                error: Some(error),
+            },
+        };
+        results.push(result);
+    }
+
+    Ok(Json(results))
+}
+
+// A known content type wins; anything else, like `application/octet-stream`,
+// falls back to the file extension and then to the content itself.
+async fn process_upload(
+    state: &ManifestState,
+    upload: Upload,
+) -> Result<(Uuid, Vec<Order>), (StatusCode, String)> {
+    let invalid = || (StatusCode::BAD_REQUEST, "Invalid manifest".to_string());
+    let body = String::from_utf8(upload.bytes.to_vec()).map_err(|_| invalid())?;
+
+    let media_type = upload
+        .content_type
+        .as_deref()
+        .and_then(MediaType::parse)
+        .or_else(|| {
+            upload
+                .file_name
+                .as_deref()
+                .and_then(MediaType::from_file_name)
+        })
+        .or_else(|| MediaType::sniff(&body))
+        .ok_or((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Unsupported manifest format".to_string(),
+        ))?;
+    let manifest: Manifest = media_type.deserialize(&body).ok_or_else(invalid)?;
+
+    state.rules.read().unwrap().check(&fields(&manifest))?;
+
+    let package = package_name(&manifest);
+    let orders = collect_orders(sources(manifest));
+    let id = history::store(&state.pool, package, &orders).await?;
+
+    Ok((id, orders))
+}
+
+pub async fn lint(
+    content_type: ContentType,
+    body: String,
+) -> Result<Json<Vec<linter::Finding>>, (StatusCode, String)> {
+    let manifest: Table = parse(content_type, &body)?;
+    Ok(Json(linter::lint(manifest)))
+}
+
+pub async fn dependencies(
+    content_type: ContentType,
+    body: String,
+) -> Result<Json<Vec<inventory::DependencyEntry>>, (StatusCode, String)> {
+    let manifest: Manifest = parse(content_type, &body)?;
+    Ok(Json(inventory::inventory(&manifest)))
+}
+
+fn parse<T: DeserializeOwned>(
+    content_type: ContentType,
+    body: &str,
+) -> Result<T, (StatusCode, String)> {
+    let media_type = content_type
+        .or_sniff(body)
+        .ok_or((StatusCode::UNSUPPORTED_MEDIA_TYPE, String::new()))?;
+
+    media_type
+        .deserialize(body)
+        .ok_or((StatusCode::BAD_REQUEST, "Invalid manifest".to_string()))
+}
+
+fn package_name(manifest: &Manifest) -> Option<String> {
+    manifest
+        .package
+        .as_ref()
+        .map(|package| package.name.clone())
+}
+
+fn sources(manifest: Manifest) -> [(Option<Value>, Source); 2] {
+    let package_metadata = manifest.package.and_then(|package| package.metadata);
+    let workspace_metadata = manifest.workspace.and_then(|workspace| workspace.metadata);
+    [
+        (package_metadata, Source::Package),
+        (workspace_metadata, Source::Workspace),
+    ]
+}
+
+fn collect_orders(sources: [(Option<Value>, Source); 2]) -> Vec<Order> {
+    sources
+        .into_iter()
+        .filter_map(|(metadata, source)| into_orders(metadata, source))
+        .flatten()
+        .collect()
+}
+
+// Orders for the same item are only merged within the same source, so the
+// origin of every merged order stays known.
+fn aggregate(orders: Vec<Order>) -> Vec<Order> {
+    let mut positions: HashMap<(Source, String), usize> = HashMap::new();
+    let mut aggregated: Vec<Order> = vec![];
+
+    for order in orders {
+        let key = (order.source, order.item.clone());
+        match positions.get(&key) {
+            Some(&position) => {
+                let merged = &mut aggregated[position];
+                merged.quantity = merged.quantity.saturating_add(order.quantity);
+            }
+            None => {
+                positions.insert(key, aggregated.len());
+                aggregated.push(order);
+            }
+        }
+    }
+
+    aggregated
+}
+
+// Orders with equal keys keep their manifest order either way.
+fn sort(orders: &mut [Order], key: SortKey, desc: bool) {
+    orders.sort_by(|x, y| {
+        let ordering = match key {
+            SortKey::Item => x.item.cmp(&y.item),
+            SortKey::Quantity => x.quantity.cmp(&y.quantity),
+        };
+        if desc {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+fn report(sources: [(Option<Value>, Source); 2]) -> Vec<OrderReport> {
+    sources
+        .into_iter()
+        .filter_map(|(metadata, source)| Some((validate_orders(metadata, source)?, source)))
+        .flat_map(|(orders, source)| {
+            orders
+                .into_iter()
+                .enumerate()
+                .map(move |(index, order)| OrderReport {
+                    index,
+                    source,
+                    verdict: match order {
+                        Ok(Order { item, quantity, .. }) => Verdict::Accepted { item, quantity },
+                        Err(rejection) => Verdict::Rejected { rejection },
+                    },
+                })
+        })
+        .collect()
+}
+
+fn fields(manifest: &Manifest) -> Fields {
+    Fields {
+        keywords: inherited(
+            manifest,
+            |package| &package.keywords,
+            |workspace| &workspace.keywords,
+        ),
+        categories: inherited(
+            manifest,
+            |package| &package.categories,
+            |workspace| &workspace.categories,
+        ),
+        license: inherited(
+            manifest,
+            |package| &package.license,
+            |workspace| &workspace.license,
+        ),
+        edition: inherited(
+            manifest,
+            |package| &package.edition,
+            |workspace| &workspace.edition,
+        ),
+        version: inherited(
+            manifest,
+            |package| &package.version,
+            |workspace| &workspace.version,
+        ),
+    }
+}
+
+// Members may inherit package fields from the workspace, and a virtual
+// manifest only has the workspace fields.
+fn inherited<T: Clone>(
+    manifest: &Manifest,
+    local: impl Fn(&Package) -> &Option<MaybeInherited<T>>,
+    workspace: impl Fn(&WorkspacePackage) -> &Option<T>,
+) -> Option<T> {
+    let workspace_value = manifest
+        .workspace
+        .as_ref()
+        .and_then(|manifest_workspace| manifest_workspace.package.as_ref())
+        .and_then(|package| workspace(package).clone());
+
+    match manifest.package.as_ref().map(local) {
+        Some(Some(MaybeInherited::Local(value))) => Some(value.clone()),
+        Some(Some(MaybeInherited::Inherited { .. })) => workspace_value,
+        Some(None) => None,
+        None => workspace_value,
+    }
+}
+
+fn into_orders(metadata: Option<Value>, source: Source) -> Option<Vec<Order>> {
+    let orders = validate_orders(metadata, source)?
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect();
+    Some(orders)
+}
+
+fn validate_orders(
+    metadata: Option<Value>,
+    source: Source,
+) -> Option<Vec<Result<Order, Rejection>>> {
+    let orders = metadata?
+        .get("orders")?
+        .as_array()?
+        .iter()
+        .map(|order| validate_order(order, source))
+        .collect();
+    Some(orders)
+}
+
+fn validate_order(order: &Value, source: Source) -> Result<Order, Rejection> {
+    let order = order.as_table().ok_or(Rejection::NotATable)?;
+
+    let item = match order.get("item") {
+        Some(Value::String(item)) => item,
+        Some(_) => return Err(Rejection::WrongType { field: "item" }),
+        None => return Err(Rejection::MissingField { field: "item" }),
+    };
+    let quantity = match order.get("quantity") {
+        Some(Value::Integer(quantity)) => *quantity,
+        Some(_) => return Err(Rejection::WrongType { field: "quantity" }),
+        None => return Err(Rejection::MissingField { field: "quantity" }),
+    };
+    let quantity = u32::try_from(quantity).map_err(|_| {
+        if quantity < 0 {
+            Rejection::Negative
+        } else {
+            Rejection::Overflow
+        }
+    })?;
+
+    Ok(Order {
+        item: item.to_owned(),
+        quantity,
+        source,
+    })
+}
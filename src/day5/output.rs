@@ -0,0 +1,99 @@
+use super::entity::Order;
+use axum::{
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+};
+use itertools::Itertools;
+use serde::Serialize;
+
+#[derive(Clone, Copy)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Yaml,
+    Toml,
+    Csv,
+}
+
+impl OutputFormat {
+    // The first supported media type listed in Accept wins.
+    pub fn negotiate(headers: &HeaderMap) -> Self {
+        headers
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .and_then(|accept| {
+                accept.split(',').find_map(|media_type| {
+                    let media_type = media_type.split(';').next().unwrap_or("").trim();
+                    match media_type {
+                        "text/plain" => Some(OutputFormat::Text),
+                        "application/json" => Some(OutputFormat::Json),
+                        "application/yaml" => Some(OutputFormat::Yaml),
+                        "application/toml" => Some(OutputFormat::Toml),
+                        "text/csv" => Some(OutputFormat::Csv),
+                        _ => None,
+                    }
+                })
+            })
+            .unwrap_or(OutputFormat::Text)
+    }
+
+    pub fn render(self, orders: &[Order], show_source: bool) -> Response {
+        match self {
+            OutputFormat::Text => orders
+                .iter()
+                .map(|order| {
+                    if show_source {
+                        format!("{order} ({})", order.source)
+                    } else {
+                        order.to_string()
+                    }
+                })
+                .join("\n")
+                .into_response(),
+            OutputFormat::Json => {
+                with_content_type("application/json", serde_json::to_string(orders).unwrap())
+            }
+            OutputFormat::Yaml => {
+                with_content_type("application/yaml", serde_yml::to_string(orders).unwrap())
+            }
+            OutputFormat::Toml => {
+                #[derive(Serialize)]
+                struct Orders<'a> {
+                    orders: &'a [Order],
+                }
+
+                with_content_type(
+                    "application/toml",
+                    toml::to_string(&Orders { orders }).unwrap(),
+                )
+            }
+            OutputFormat::Csv => {
+                let rows = orders.iter().map(|order| {
+                    format!(
+                        "{},{},{}",
+                        csv_field(&order.item),
+                        order.quantity,
+                        order.source
+                    )
+                });
+                let csv = std::iter::once("item,quantity,source".to_string())
+                    .chain(rows)
+                    .join("\n");
+
+                with_content_type("text/csv", csv + "\n")
+            }
+        }
+    }
+}
+
+fn with_content_type(content_type: &'static str, body: String) -> Response {
+    ([(header::CONTENT_TYPE, content_type)], body).into_response()
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
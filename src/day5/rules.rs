@@ -0,0 +1,235 @@
+use axum::http::StatusCode;
+use cargo_manifest::Edition;
+use itertools::Itertools;
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use std::{fmt::Display, fs};
+
+pub const RULES_PATH: &str = "manifest_rules.toml";
+
+// The default rule keeps the body /5/manifest answered with before rules were
+// configurable, so it's reported by its message alone.
+const MAGIC_KEYWORD_RULE: &str = "magic-keyword";
+
+#[derive(Deserialize)]
+pub struct Rules {
+    #[serde(default, rename = "rule")]
+    rules: Vec<Rule>,
+}
+
+// A failing rule reports its name, followed by its message when it has one.
+#[derive(Deserialize)]
+struct Rule {
+    name: String,
+    message: Option<String>,
+    #[serde(flatten)]
+    check: Check,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "check", rename_all = "snake_case")]
+enum Check {
+    Keywords {
+        require: Vec<String>,
+    },
+    Categories {
+        require: Vec<String>,
+    },
+    License {
+        allow: Vec<String>,
+    },
+    Edition {
+        min: Option<Edition>,
+        max: Option<Edition>,
+    },
+    Version {
+        req: VersionReq,
+    },
+}
+
+// The package fields rules are checked against, with workspace inheritance
+// already resolved.
+pub struct Fields {
+    pub keywords: Option<Vec<String>>,
+    pub categories: Option<Vec<String>>,
+    pub license: Option<String>,
+    pub edition: Option<Edition>,
+    pub version: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum RulesError {
+    Io(std::io::Error),
+    Parse(toml::de::Error),
+}
+
+impl Display for RulesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RulesError::Io(error) => write!(f, "failed to read {RULES_PATH}: {error}"),
+            RulesError::Parse(error) => write!(f, "failed to parse {RULES_PATH}: {error}"),
+        }
+    }
+}
+
+impl From<RulesError> for (StatusCode, String) {
+    fn from(value: RulesError) -> Self {
+        (StatusCode::INTERNAL_SERVER_ERROR, value.to_string())
+    }
+}
+
+pub struct RuleFailures(Vec<String>);
+
+impl From<RuleFailures> for (StatusCode, String) {
+    fn from(value: RuleFailures) -> Self {
+        (StatusCode::BAD_REQUEST, value.0.join("\n"))
+    }
+}
+
+impl Rules {
+    pub fn load() -> Result<Self, RulesError> {
+        let rules = fs::read_to_string(RULES_PATH).map_err(RulesError::Io)?;
+        toml::from_str(&rules).map_err(RulesError::Parse)
+    }
+
+    pub fn check(&self, fields: &Fields) -> Result<(), RuleFailures> {
+        let failures = self
+            .rules
+            .iter()
+            .filter(|rule| !rule.check.passes(fields))
+            .map(Rule::failure)
+            .collect_vec();
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(RuleFailures(failures))
+        }
+    }
+}
+
+impl Rule {
+    fn failure(&self) -> String {
+        match &self.message {
+            Some(message) if self.name == MAGIC_KEYWORD_RULE => message.clone(),
+            Some(message) => format!("{}: {message}", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+impl Check {
+    // A missing edition or version is read the way Cargo reads it.
+    fn passes(&self, fields: &Fields) -> bool {
+        match self {
+            Check::Keywords { require } => contains_all(&fields.keywords, require),
+            Check::Categories { require } => contains_all(&fields.categories, require),
+            Check::License { allow } => fields
+                .license
+                .as_ref()
+                .is_some_and(|license| allow.contains(license)),
+            Check::Edition { min, max } => {
+                let edition = rank(fields.edition.unwrap_or(Edition::E2015));
+                min.is_none_or(|min| edition >= rank(min))
+                    && max.is_none_or(|max| edition <= rank(max))
+            }
+            Check::Version { req } => {
+                let version = fields.version.as_deref().unwrap_or("0.0.0");
+                Version::parse(version).is_ok_and(|version| req.matches(&version))
+            }
+        }
+    }
+}
+
+// Editions aren't ordered by cargo_manifest, but their names are years.
+fn rank(edition: Edition) -> u16 {
+    edition.as_str().parse().unwrap()
+}
+
+fn contains_all(values: &Option<Vec<String>>, required: &[String]) -> bool {
+    values
+        .as_ref()
+        .is_some_and(|values| required.iter().all(|value| values.contains(value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(edition: Option<Edition>) -> Fields {
+        Fields {
+            keywords: None,
+            categories: None,
+            license: None,
+            edition,
+            version: None,
+        }
+    }
+
+    fn failures(rules: &str, fields: &Fields) -> Vec<String> {
+        let rules = toml::from_str::<Rules>(rules).unwrap();
+        rules
+            .check(fields)
+            .err()
+            .map_or_else(Vec::new, |failures| failures.0)
+    }
+
+    #[test]
+    fn failures_are_named() {
+        let rules = r#"
+            [[rule]]
+            name = "magic-keyword"
+            message = "Magic keyword not provided"
+            check = "keywords"
+            require = ["Christmas 2024"]
+
+            [[rule]]
+            name = "license"
+            message = "License not allowed"
+            check = "license"
+            allow = ["MIT"]
+
+            [[rule]]
+            name = "modern"
+            check = "edition"
+            min = "2021"
+        "#;
+
+        assert_eq!(
+            failures(rules, &fields(None)),
+            [
+                "Magic keyword not provided",
+                "license: License not allowed",
+                "modern",
+            ]
+        );
+    }
+
+    #[test]
+    fn edition_bounds_are_inclusive() {
+        let check = Check::Edition {
+            min: Some(Edition::E2018),
+            max: Some(Edition::E2021),
+        };
+
+        assert!(!check.passes(&fields(Some(Edition::E2015))));
+        assert!(check.passes(&fields(Some(Edition::E2018))));
+        assert!(check.passes(&fields(Some(Edition::E2021))));
+        assert!(!check.passes(&fields(Some(Edition::E2024))));
+    }
+
+    #[test]
+    fn missing_edition_is_2015() {
+        let min = Check::Edition {
+            min: Some(Edition::E2018),
+            max: None,
+        };
+        let max = Check::Edition {
+            min: None,
+            max: Some(Edition::E2015),
+        };
+
+        assert!(!min.passes(&fields(None)));
+        assert!(max.passes(&fields(None)));
+    }
+}
@@ -0,0 +1,7 @@
+use sqlx::PgPool;
+
+// The scheme has several statements, which a prepared query can't run.
+pub async fn create_tables(pool: &PgPool) {
+    let sql = include_str!("scheme.sql");
+    sqlx::raw_sql(sql).execute(pool).await.unwrap();
+}
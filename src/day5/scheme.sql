@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS manifest_submissions (
+    id UUID PRIMARY KEY,
+    package TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS manifest_orders (
+    submission_id UUID NOT NULL REFERENCES manifest_submissions (id) ON DELETE CASCADE,
+    position INT NOT NULL,
+    item TEXT NOT NULL,
+    quantity BIGINT NOT NULL,
+    source TEXT NOT NULL,
+    PRIMARY KEY (submission_id, position)
+);
+
+CREATE INDEX IF NOT EXISTS manifest_submissions_created_at
+    ON manifest_submissions (created_at);
@@ -0,0 +1,285 @@
+use super::{client::ClientKey, config::BucketConfig};
+use axum::http::{header, HeaderMap};
+use itertools::Itertools;
+use serde::{Serialize, Serializer};
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::{Duration, Instant},
+};
+
+const MAX_CLIENTS: usize = 10_000;
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Tokens are added lazily, whenever the bucket is looked at.
+pub struct TokenBucket {
+    tokens: u32,
+    last_refill: Instant,
+}
+
+#[derive(Serialize)]
+pub struct BucketStatus {
+    tokens: u32,
+    capacity: u32,
+    refill: u32,
+    #[serde(rename = "refill_interval_ms", serialize_with = "millis")]
+    refill_interval: Duration,
+    #[serde(rename = "next_token_in_ms", serialize_with = "optional_millis")]
+    next_token_in: Option<Duration>,
+    #[serde(rename = "full_in_ms", serialize_with = "millis")]
+    full_in: Duration,
+}
+
+impl TokenBucket {
+    fn new(config: &BucketConfig, now: Instant) -> Self {
+        TokenBucket {
+            tokens: config.initial,
+            last_refill: now,
+        }
+    }
+
+    fn update(&mut self, config: &BucketConfig, now: Instant) {
+        let intervals =
+            now.duration_since(self.last_refill).as_nanos() / config.interval.as_nanos();
+        let intervals = u32::try_from(intervals).unwrap_or(u32::MAX);
+
+        self.tokens = intervals
+            .saturating_mul(config.refill)
+            .saturating_add(self.tokens)
+            .min(config.capacity);
+        if self.tokens == config.capacity {
+            self.last_refill = now;
+        } else {
+            self.last_refill += config.interval * intervals;
+        }
+    }
+
+    // Takes as many tokens as are available up to `max`, as long as that's at
+    // least `min`, and returns how many were taken.
+    fn try_acquire(&mut self, config: &BucketConfig, min: u32, max: u32) -> u32 {
+        self.update(config, Instant::now());
+        if self.tokens < min {
+            return 0;
+        }
+
+        let acquired = self.tokens.min(max);
+        self.tokens -= acquired;
+        acquired
+    }
+
+    fn is_full(&mut self, config: &BucketConfig, now: Instant) -> bool {
+        self.update(config, now);
+        self.tokens == config.capacity
+    }
+
+    fn status(&mut self, config: &BucketConfig) -> BucketStatus {
+        let now = Instant::now();
+        self.update(config, now);
+
+        let next_token_in = (self.tokens < config.capacity).then(|| {
+            config
+                .interval
+                .saturating_sub(now.duration_since(self.last_refill))
+        });
+        let full_in = next_token_in.map_or(Duration::ZERO, |next_token_in| {
+            let missing = config.capacity - self.tokens;
+            next_token_in + config.interval * (missing.div_ceil(config.refill) - 1)
+        });
+
+        BucketStatus {
+            tokens: self.tokens,
+            capacity: config.capacity,
+            refill: config.refill,
+            refill_interval: config.interval,
+            next_token_in,
+            full_in,
+        }
+    }
+}
+
+impl BucketStatus {
+    pub fn tokens(&self) -> u32 {
+        self.tokens
+    }
+
+    // `None` when the bucket can never hold that many tokens.
+    pub fn available_in(&self, tokens: u32) -> Option<Duration> {
+        if tokens > self.capacity {
+            return None;
+        }
+
+        match (tokens.checked_sub(self.tokens), self.next_token_in) {
+            (Some(missing @ 1..), Some(next_token_in)) => {
+                Some(next_token_in + self.refill_interval * (missing.div_ceil(self.refill) - 1))
+            }
+            _ => Some(Duration::ZERO),
+        }
+    }
+
+    // `Retry-After` is only sent while the bucket is empty, when the next
+    // request would be turned away.
+    pub fn headers(&self) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("RateLimit-Limit", self.capacity.into());
+        headers.insert("RateLimit-Remaining", self.tokens.into());
+        headers.insert("RateLimit-Reset", seconds(self.full_in).into());
+
+        if let (0, Some(next_token_in)) = (self.tokens, self.next_token_in) {
+            headers.insert(header::RETRY_AFTER, seconds(next_token_in).into());
+        }
+
+        headers
+    }
+
+    // A request can be turned away with tokens left, when it wants more.
+    pub fn rejection_headers(&self, tokens: u32) -> HeaderMap {
+        let mut headers = self.headers();
+        if let Some(available_in) = self.available_in(tokens) {
+            headers.insert(header::RETRY_AFTER, seconds(available_in).into());
+        }
+
+        headers
+    }
+}
+
+// Header values are whole seconds, rounded up so clients don't retry early.
+fn seconds(duration: Duration) -> u64 {
+    duration.as_millis().div_ceil(1000) as u64
+}
+
+pub fn millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+fn optional_millis<S: Serializer>(
+    duration: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => millis(duration, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+struct Entry {
+    bucket: TokenBucket,
+    last_used: Instant,
+    recency: u64,
+}
+
+// `recency` orders clients from least to most recently used, so neither the
+// idle sweep nor evicting for room has to look at every bucket.
+pub struct MilkBuckets {
+    config: BucketConfig,
+    entries: HashMap<ClientKey, Entry>,
+    recency: BTreeMap<u64, ClientKey>,
+    next_use: u64,
+    last_sweep: Instant,
+}
+
+impl MilkBuckets {
+    pub fn new() -> Self {
+        MilkBuckets {
+            config: BucketConfig::default(),
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            next_use: 0,
+            last_sweep: Instant::now(),
+        }
+    }
+
+    pub fn config(&self) -> BucketConfig {
+        self.config
+    }
+
+    pub fn try_acquire(&mut self, client: ClientKey, min: u32, max: u32) -> (u32, BucketStatus) {
+        let now = Instant::now();
+        self.evict(now, &client);
+
+        let use_id = self.next_use;
+        self.next_use += 1;
+
+        let config = &self.config;
+        let entry = match self.entries.get_mut(&client) {
+            Some(entry) => {
+                self.recency.remove(&entry.recency);
+                entry
+            }
+            None => self.entries.entry(client.clone()).or_insert(Entry {
+                bucket: TokenBucket::new(config, now),
+                last_used: now,
+                recency: use_id,
+            }),
+        };
+        entry.last_used = now;
+        entry.recency = use_id;
+        self.recency.insert(use_id, client);
+
+        let acquired = entry.bucket.try_acquire(config, min, max);
+        (acquired, entry.bucket.status(config))
+    }
+
+    // Looking at a bucket doesn't create one, nor count as using it.
+    pub fn status(&mut self, client: &ClientKey) -> BucketStatus {
+        match self.entries.get_mut(client) {
+            Some(entry) => entry.bucket.status(&self.config),
+            None => TokenBucket::new(&self.config, Instant::now()).status(&self.config),
+        }
+    }
+
+    pub fn refill(&mut self, client: &ClientKey) {
+        if let Some(entry) = self.entries.get_mut(client) {
+            entry.bucket = TokenBucket::new(&self.config, Instant::now());
+        }
+    }
+
+    // Buckets are changed in place: tokens earned under the old config are
+    // added first, and anything above the new capacity is dropped.
+    pub fn configure(&mut self, config: BucketConfig) {
+        let now = Instant::now();
+        for entry in self.entries.values_mut() {
+            entry.bucket.update(&self.config, now);
+            entry.bucket.tokens = entry.bucket.tokens.min(config.capacity);
+        }
+        self.config = config;
+    }
+
+    // Idle buckets are swept at most once per timeout, walking only the ones
+    // unused for that long. Only full buckets count as idle, since a new one
+    // may start lower. When there's still no room for a new client, the least
+    // recently used one goes.
+    fn evict(&mut self, now: Instant, client: &ClientKey) {
+        if now.duration_since(self.last_sweep) >= IDLE_TIMEOUT {
+            let idle = self
+                .recency
+                .values()
+                .map(|client| (client, &self.entries[client]))
+                .take_while(|(_, entry)| now.duration_since(entry.last_used) >= IDLE_TIMEOUT)
+                .map(|(client, _)| client.clone())
+                .collect_vec();
+            for client in idle {
+                if self
+                    .entries
+                    .get_mut(&client)
+                    .unwrap()
+                    .bucket
+                    .is_full(&self.config, now)
+                {
+                    self.remove(&client);
+                }
+            }
+            self.last_sweep = now;
+        }
+
+        if !self.entries.contains_key(client) && self.entries.len() >= MAX_CLIENTS {
+            if let Some((_, oldest)) = self.recency.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+
+    fn remove(&mut self, client: &ClientKey) {
+        if let Some(entry) = self.entries.remove(client) {
+            self.recency.remove(&entry.recency);
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use super::MilkState;
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts, HeaderMap},
+};
+use jsonwebtoken::{DecodingKey, Validation};
+use serde::Deserialize;
+use std::{
+    convert::Infallible,
+    fmt::Display,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum ClientKey {
+    Subject(String),
+    ClientId(String),
+    Ip(IpAddr),
+    Anonymous,
+}
+
+// Prefixed by kind, so a client id can't pass for a subject in the ledger.
+impl Display for ClientKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientKey::Subject(subject) => write!(f, "sub:{subject}"),
+            ClientKey::ClientId(client_id) => write!(f, "id:{client_id}"),
+            ClientKey::Ip(ip) => write!(f, "ip:{ip}"),
+            ClientKey::Anonymous => write!(f, "anonymous"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<MilkState>> for ClientKey {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<MilkState>,
+    ) -> Result<Self, Self::Rejection> {
+        let headers = &parts.headers;
+        let key = state
+            .jwt_key
+            .as_ref()
+            .and_then(|key| subject(headers, key))
+            .map(ClientKey::Subject)
+            .or_else(|| client_id(headers).map(ClientKey::ClientId))
+            .or_else(|| ip(parts).map(ClientKey::Ip))
+            .unwrap_or(ClientKey::Anonymous);
+
+        Ok(key)
+    }
+}
+
+// Only tokens signed with the configured key name a subject, so a client can't
+// pick someone else's bucket by forging one.
+fn subject(headers: &HeaderMap, key: &DecodingKey) -> Option<String> {
+    let token = headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")?;
+
+    let mut validation = Validation::default();
+    validation.required_spec_claims.remove("exp");
+
+    let claims = jsonwebtoken::decode::<Claims>(token.trim(), key, &validation).ok()?;
+    Some(claims.claims.sub)
+}
+
+// Anyone can send this header, so it's only meant for trusted callers, like
+// services behind the same proxy, that pick distinct ids.
+fn client_id(headers: &HeaderMap) -> Option<String> {
+    let client_id = headers.get("X-Client-Id")?.to_str().ok()?.trim();
+    (!client_id.is_empty()).then(|| client_id.to_string())
+}
+
+// The service runs behind a proxy, which appends the peer address to
+// `X-Forwarded-For`. Clients can send entries of their own, or an
+// `X-Real-IP`, so only the last entry is used. Without one, the connection's
+// own peer is used when the server records it.
+fn ip(parts: &Parts) -> Option<IpAddr> {
+    let forwarded_for = parts
+        .headers
+        .get_all("X-Forwarded-For")
+        .iter()
+        .next_back()
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.rsplit(',').next())
+        .and_then(|value| value.trim().parse().ok());
+
+    forwarded_for.or_else(|| {
+        let ConnectInfo(peer) = parts.extensions.get::<ConnectInfo<SocketAddr>>()?;
+        Some(peer.ip())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+    use jsonwebtoken::{EncodingKey, Header};
+
+    fn parts(headers: &[(&str, &str)], peer: Option<&str>) -> Parts {
+        let mut request = Request::builder();
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let (mut parts, ()) = request.body(()).unwrap().into_parts();
+        if let Some(peer) = peer {
+            let peer: SocketAddr = peer.parse().unwrap();
+            parts.extensions.insert(ConnectInfo(peer));
+        }
+        parts
+    }
+
+    #[test]
+    fn only_the_last_forwarded_entry_counts() {
+        let parts = parts(
+            &[
+                ("X-Real-IP", "10.0.0.1"),
+                ("X-Forwarded-For", "10.0.0.2, 192.0.2.7"),
+            ],
+            None,
+        );
+
+        assert_eq!(ip(&parts), Some("192.0.2.7".parse().unwrap()));
+    }
+
+    #[test]
+    fn peer_address_is_the_fallback() {
+        let parts = parts(&[("X-Real-IP", "10.0.0.1")], Some("192.0.2.7:4321"));
+
+        assert_eq!(ip(&parts), Some("192.0.2.7".parse().unwrap()));
+    }
+
+    #[test]
+    fn subject_needs_a_valid_signature() {
+        let claims = serde_json::json!({ "sub": "rudolph" });
+        let token = |secret: &[u8]| {
+            let key = EncodingKey::from_secret(secret);
+            let token = jsonwebtoken::encode(&Header::default(), &claims, &key).unwrap();
+            parts(&[("Authorization", &format!("Bearer {token}"))], None)
+        };
+        let key = DecodingKey::from_secret(b"north pole");
+
+        let signed = token(b"north pole");
+        assert_eq!(subject(&signed.headers, &key).as_deref(), Some("rudolph"));
+        let forged = token(b"south pole");
+        assert_eq!(subject(&forged.headers, &key), None);
+    }
+}
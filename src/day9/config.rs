@@ -0,0 +1,84 @@
+use super::bucket::millis;
+use axum::http::StatusCode;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, time::Duration};
+
+const MAX_INTERVAL: Duration = Duration::from_secs(3600);
+
+#[derive(Serialize, Clone, Copy)]
+pub struct BucketConfig {
+    pub capacity: u32,
+    pub initial: u32,
+    pub refill: u32,
+    #[serde(rename = "interval_ms", serialize_with = "millis")]
+    pub interval: Duration,
+}
+
+impl Default for BucketConfig {
+    fn default() -> Self {
+        BucketConfig {
+            capacity: 5,
+            initial: 5,
+            refill: 1,
+            interval: Duration::from_secs(1),
+        }
+    }
+}
+
+// Fields left out keep their current value.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigUpdate {
+    capacity: Option<u32>,
+    initial: Option<u32>,
+    refill: Option<u32>,
+    interval_ms: Option<u64>,
+}
+
+pub struct ConfigError(Vec<&'static str>);
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.iter().join("\n"))
+    }
+}
+
+impl From<ConfigError> for (StatusCode, String) {
+    fn from(value: ConfigError) -> Self {
+        (StatusCode::BAD_REQUEST, value.to_string())
+    }
+}
+
+impl ConfigUpdate {
+    pub fn apply(self, config: &BucketConfig) -> Result<BucketConfig, ConfigError> {
+        let updated = BucketConfig {
+            capacity: self.capacity.unwrap_or(config.capacity),
+            initial: self.initial.unwrap_or(config.initial),
+            refill: self.refill.unwrap_or(config.refill),
+            interval: self
+                .interval_ms
+                .map_or(config.interval, Duration::from_millis),
+        };
+
+        let mut errors = vec![];
+        if updated.capacity == 0 {
+            errors.push("capacity must be at least 1");
+        }
+        if updated.initial > updated.capacity {
+            errors.push("initial must not exceed capacity");
+        }
+        if updated.refill == 0 {
+            errors.push("refill must be at least 1");
+        }
+        if updated.interval.is_zero() || updated.interval > MAX_INTERVAL {
+            errors.push("interval_ms must be between 1 and 3600000");
+        }
+
+        if errors.is_empty() {
+            Ok(updated)
+        } else {
+            Err(ConfigError(errors))
+        }
+    }
+}
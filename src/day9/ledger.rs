@@ -0,0 +1,137 @@
+use super::{client::ClientKey, MilkState};
+use crate::paging;
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    types::chrono::{DateTime, Utc},
+    FromRow, PgPool,
+};
+use std::{fmt::Display, sync::Arc};
+
+#[derive(Clone, Copy)]
+pub enum Kind {
+    Withdrawal,
+    Refill,
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Kind::Withdrawal => write!(f, "withdrawal"),
+            Kind::Refill => write!(f, "refill"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LedgerParams {
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    client: Option<String>,
+    #[serde(default)]
+    aggregate: bool,
+    limit: Option<i64>,
+    #[serde(default)]
+    offset: i64,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct LedgerEntry {
+    id: i64,
+    created_at: DateTime<Utc>,
+    client: String,
+    kind: String,
+    units: i64,
+    remaining: i64,
+}
+
+#[derive(Serialize, FromRow)]
+pub struct ClientUsage {
+    client: String,
+    withdrawals: i64,
+    withdrawn: i64,
+    refills: i64,
+    refilled: i64,
+    last_seen: DateTime<Utc>,
+}
+
+// The milk has changed hands by the time it's recorded, so a failed write is
+// only logged rather than failing the request.
+pub async fn record(pool: &PgPool, client: &ClientKey, kind: Kind, units: u32, remaining: u32) {
+    let result = sqlx::query(
+        r#"INSERT INTO milk_ledger (client, kind, units, remaining)
+        VALUES ($1, $2, $3, $4)"#,
+    )
+    .bind(client.to_string())
+    .bind(kind.to_string())
+    .bind(i64::from(units))
+    .bind(i64::from(remaining))
+    .execute(pool)
+    .await;
+
+    if let Err(error) = result {
+        tracing::error!("failed to record milk {kind} for {client}: {error}");
+    }
+}
+
+// Entries come newest first, or summed up per client with `aggregate`. The
+// `from` and `to` bounds work like those of `/5/totals`.
+pub async fn ledger(
+    State(state): State<Arc<MilkState>>,
+    Query(params): Query<LedgerParams>,
+) -> Result<Response, StatusCode> {
+    let limit = paging::limit(params.limit, params.offset)?;
+
+    if params.aggregate {
+        let usage = sqlx::query_as::<_, ClientUsage>(
+            r#"SELECT client,
+                COUNT(*) FILTER (WHERE kind = 'withdrawal') AS withdrawals,
+                COALESCE(SUM(units) FILTER (WHERE kind = 'withdrawal'), 0)::BIGINT AS withdrawn,
+                COUNT(*) FILTER (WHERE kind = 'refill') AS refills,
+                COALESCE(SUM(units) FILTER (WHERE kind = 'refill'), 0)::BIGINT AS refilled,
+                MAX(created_at) AS last_seen
+            FROM milk_ledger
+            WHERE ($1::TIMESTAMPTZ IS NULL OR created_at >= $1)
+              AND ($2::TIMESTAMPTZ IS NULL OR created_at < $2)
+              AND ($3::TEXT IS NULL OR client = $3)
+            GROUP BY client
+            ORDER BY withdrawn DESC, client
+            LIMIT $4 OFFSET $5"#,
+        )
+        .bind(params.from)
+        .bind(params.to)
+        .bind(params.client)
+        .bind(limit)
+        .bind(params.offset)
+        .fetch_all(&state.pool)
+        .await
+        .unwrap();
+
+        return Ok(Json(usage).into_response());
+    }
+
+    let entries = sqlx::query_as::<_, LedgerEntry>(
+        r#"SELECT id, created_at, client, kind, units, remaining
+        FROM milk_ledger
+        WHERE ($1::TIMESTAMPTZ IS NULL OR created_at >= $1)
+          AND ($2::TIMESTAMPTZ IS NULL OR created_at < $2)
+          AND ($3::TEXT IS NULL OR client = $3)
+        ORDER BY created_at DESC, id DESC
+        LIMIT $4 OFFSET $5"#,
+    )
+    .bind(params.from)
+    .bind(params.to)
+    .bind(params.client)
+    .bind(limit)
+    .bind(params.offset)
+    .fetch_all(&state.pool)
+    .await
+    .unwrap();
+
+    Ok(Json(entries).into_response())
+}
@@ -0,0 +1,211 @@
+mod bucket;
+mod client;
+mod config;
+mod ledger;
+mod quantity;
+mod request;
+mod scheme;
+mod volume;
+mod wait;
+
+use crate::{admin, media_type::ContentType};
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use bucket::{BucketStatus, MilkBuckets};
+use client::ClientKey;
+use config::{BucketConfig, ConfigUpdate};
+use jsonwebtoken::DecodingKey;
+pub use ledger::ledger;
+use ledger::Kind;
+use quantity::ConvertParams;
+use request::{MilkParams, MilkRequest, Mode};
+pub use scheme::create_tables;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    sync::Mutex,
+    time::{self, Instant},
+};
+use volume::Volume;
+
+pub struct MilkState {
+    pub pool: PgPool,
+    buckets: Mutex<MilkBuckets>,
+    admin_secret: Option<String>,
+    jwt_key: Option<DecodingKey>,
+}
+
+// Without an admin secret, the buckets keep their default config, and without
+// a JWT secret, clients aren't told apart by token subject.
+pub fn create_milk_state(
+    pool: PgPool,
+    admin_secret: Option<String>,
+    jwt_secret: Option<String>,
+) -> MilkState {
+    MilkState {
+        pool,
+        buckets: Mutex::new(MilkBuckets::new()),
+        admin_secret,
+        jwt_key: jwt_secret.map(|secret| DecodingKey::from_secret(secret.as_bytes())),
+    }
+}
+
+#[derive(Serialize)]
+struct Withdrawn {
+    requested: u32,
+    withdrawn: u32,
+}
+
+pub async fn milk(
+    State(state): State<Arc<MilkState>>,
+    Query(params): Query<MilkParams>,
+    client: ClientKey,
+    content_type: ContentType,
+    body: String,
+) -> Result<Response, (StatusCode, String)> {
+    let request = MilkRequest::parse(&content_type, &body, params.mode)?;
+
+    // Conversions take milk too, but aren't turned away without it.
+    let (units, mode, is_json) = match request {
+        MilkRequest::Convert(volume) => {
+            let (acquired, status) = state.buckets.lock().await.try_acquire(client.clone(), 1, 1);
+            if acquired > 0 {
+                let remaining = status.tokens();
+                ledger::record(&state.pool, &client, Kind::Withdrawal, acquired, remaining).await;
+            }
+            let volume = serde_json::to_string(&volume).unwrap();
+            return Ok((status.headers(), volume).into_response());
+        }
+        MilkRequest::Withdraw {
+            units,
+            mode,
+            is_json,
+        } => (units, mode, is_json),
+    };
+
+    let capacity = state.buckets.lock().await.config().capacity;
+    if mode == Mode::All && units > capacity {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("{units} units requested, but the bucket only holds {capacity}\n"),
+        ));
+    }
+
+    let min = match mode {
+        Mode::All => units,
+        Mode::Partial => 1,
+    };
+    let wait = params.wait.unwrap_or_default();
+    let (withdrawn, status) = acquire(&state.buckets, client.clone(), min, units, wait).await;
+    if withdrawn > 0 {
+        let remaining = status.tokens();
+        ledger::record(&state.pool, &client, Kind::Withdrawal, withdrawn, remaining).await;
+    }
+
+    let result = match (withdrawn, is_json) {
+        (0, _) => Err((StatusCode::TOO_MANY_REQUESTS, "No milk available\n")),
+        (_, true) => Ok(Json(Withdrawn {
+            requested: units,
+            withdrawn,
+        })
+        .into_response()),
+        (1, false) if units == 1 => Ok("Milk withdrawn\n".into_response()),
+        (_, false) if withdrawn == units => {
+            Ok(format!("{units} units of milk withdrawn\n").into_response())
+        }
+        (_, false) => {
+            Ok(format!("{withdrawn} of {units} units of milk withdrawn\n").into_response())
+        }
+    };
+
+    let headers = if withdrawn == 0 {
+        status.rejection_headers(min)
+    } else {
+        status.headers()
+    };
+    Ok((headers, result).into_response())
+}
+
+// The lock is only held while looking at the bucket, never while sleeping, and
+// tokens due after the deadline aren't waited for at all.
+async fn acquire(
+    milk_buckets: &Mutex<MilkBuckets>,
+    client: ClientKey,
+    min: u32,
+    max: u32,
+    wait: Duration,
+) -> (u32, BucketStatus) {
+    let deadline = Instant::now() + wait;
+
+    loop {
+        let (acquired, status) = milk_buckets
+            .lock()
+            .await
+            .try_acquire(client.clone(), min, max);
+        let available_at = status
+            .available_in(min)
+            .map(|available_in| Instant::now() + available_in);
+
+        match available_at {
+            Some(available_at) if acquired == 0 && available_at <= deadline => {
+                time::sleep_until(available_at).await;
+            }
+            _ => return (acquired, status),
+        }
+    }
+}
+
+// The ledger is only written to once the buckets are unlocked again.
+pub async fn refill(State(state): State<Arc<MilkState>>, client: ClientKey) -> StatusCode {
+    let (before, after) = {
+        let mut milk_buckets = state.buckets.lock().await;
+        let before = milk_buckets.status(&client);
+        milk_buckets.refill(&client);
+        (before, milk_buckets.status(&client))
+    };
+
+    // Every refill is recorded, even one that found the bucket already full.
+    let refilled = after.tokens().saturating_sub(before.tokens());
+    ledger::record(&state.pool, &client, Kind::Refill, refilled, after.tokens()).await;
+
+    StatusCode::OK
+}
+
+// Changes every client's bucket, so it takes the admin secret rather than a
+// client key.
+pub async fn configure(
+    State(state): State<Arc<MilkState>>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<BucketConfig>, (StatusCode, String)> {
+    admin::authorize(&headers, state.admin_secret.as_deref(), "milk config")?;
+
+    let update = serde_json::from_str::<ConfigUpdate>(&body)
+        .map_err(|error| (StatusCode::BAD_REQUEST, error.to_string()))?;
+
+    let mut milk_buckets = state.buckets.lock().await;
+    let config = update.apply(&milk_buckets.config())?;
+    milk_buckets.configure(config);
+
+    Ok(Json(config))
+}
+
+pub async fn status(State(state): State<Arc<MilkState>>, client: ClientKey) -> impl IntoResponse {
+    let status = state.buckets.lock().await.status(&client);
+    (status.headers(), Json(status))
+}
+
+// Sums quantities in mixed units; unlike `/9/milk`, this doesn't take milk.
+pub async fn convert(
+    Query(params): Query<ConvertParams>,
+    content_type: ContentType,
+    body: String,
+) -> Result<Json<Volume>, (StatusCode, String)> {
+    let volumes = quantity::parse(&content_type, &body)?;
+    Ok(Json(quantity::total(&volumes, &params)?))
+}
@@ -0,0 +1,130 @@
+use super::volume::{Unit, Volume};
+use crate::media_type::{ContentType, MediaType};
+use axum::http::StatusCode;
+use serde::Deserialize;
+
+const MAX_DECIMALS: u32 = 12;
+
+#[derive(Deserialize)]
+pub struct ConvertParams {
+    pub to: Unit,
+    pub decimals: Option<u32>,
+    #[serde(default)]
+    pub rounding: Rounding,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    #[default]
+    Nearest,
+    Up,
+    Down,
+}
+
+// Array entries may be volumes or the same text the plain body takes.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Quantity {
+    Volume(Volume),
+    Text(String),
+}
+
+// A JSON body is an array of quantities, anything else is text like
+// `3.5 gal + 2 pints`.
+pub fn parse(content_type: &ContentType, body: &str) -> Result<Vec<Volume>, (StatusCode, String)> {
+    let volumes = match content_type.or_sniff(body) {
+        Some(MediaType::Json) => serde_json::from_str::<Vec<Quantity>>(body)
+            .map_err(|error| (StatusCode::BAD_REQUEST, error.to_string()))?
+            .into_iter()
+            .map(|quantity| match quantity {
+                Quantity::Volume(volume) => Ok(vec![volume]),
+                Quantity::Text(text) => parse_text(&text),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| (StatusCode::BAD_REQUEST, error))?
+            .concat(),
+        _ => parse_text(body).map_err(|error| (StatusCode::BAD_REQUEST, error))?,
+    };
+
+    if volumes.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "no quantities given".to_string()));
+    }
+    Ok(volumes)
+}
+
+fn parse_text(text: &str) -> Result<Vec<Volume>, String> {
+    text.split('+')
+        .filter(|term| !term.trim().is_empty())
+        .map(parse_term)
+        .collect()
+}
+
+// The amount may be a mixed number like `1 1/2`, and may touch the unit, as in
+// `500ml`.
+fn parse_term(term: &str) -> Result<Volume, String> {
+    let term = term.trim();
+    let split = term
+        .find(|c: char| c.is_alphabetic())
+        .ok_or_else(|| format!("missing unit in `{term}`"))?;
+    let (amount, unit) = term.split_at(split);
+
+    let amount = amount
+        .split_whitespace()
+        .map(parse_number)
+        .sum::<Option<f64>>()
+        .filter(|_| !amount.trim().is_empty())
+        .ok_or_else(|| format!("invalid amount in `{term}`"))?;
+
+    Ok(Volume {
+        amount,
+        unit: unit.parse()?,
+    })
+}
+
+fn parse_number(number: &str) -> Option<f64> {
+    let value = match number.split_once('/') {
+        Some((numerator, denominator)) => {
+            let denominator = denominator.parse::<f64>().ok().filter(|d| *d != 0.0)?;
+            numerator.parse::<f64>().ok()? / denominator
+        }
+        None => number.parse().ok()?,
+    };
+    value.is_finite().then_some(value)
+}
+
+pub fn total(volumes: &[Volume], params: &ConvertParams) -> Result<Volume, (StatusCode, String)> {
+    let amount = volumes
+        .iter()
+        .map(|volume| volume.to(params.to).amount)
+        .sum::<f64>();
+
+    let amount = match params.decimals {
+        Some(decimals) if decimals > MAX_DECIMALS => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("decimals must be at most {MAX_DECIMALS}"),
+            ))
+        }
+        Some(decimals) => round(amount, decimals, params.rounding),
+        None => amount,
+    };
+
+    Ok(Volume {
+        amount,
+        unit: params.to,
+    })
+}
+
+fn round(amount: f64, decimals: u32, rounding: Rounding) -> f64 {
+    // Sums like 0.1 + 0.2 are a hair off, which mustn't push them up or down a
+    // whole step.
+    let factor = 10f64.powi(decimals as i32);
+    let scaled = (amount * factor * 1e6).round() / 1e6;
+    let rounded = match rounding {
+        Rounding::Nearest => scaled.round(),
+        Rounding::Up => scaled.ceil(),
+        Rounding::Down => scaled.floor(),
+    };
+    rounded / factor
+}
@@ -0,0 +1,76 @@
+use super::{
+    volume::{Conversion, Volume},
+    wait::parse_wait,
+};
+use crate::media_type::{ContentType, MediaType};
+use axum::http::StatusCode;
+use serde::Deserialize;
+use std::time::Duration;
+
+#[derive(Deserialize)]
+pub struct MilkParams {
+    #[serde(default, deserialize_with = "parse_wait")]
+    pub wait: Option<Duration>,
+    pub mode: Option<Mode>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    #[default]
+    All,
+    Partial,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Withdrawal {
+    units: u32,
+    mode: Option<Mode>,
+}
+
+pub enum MilkRequest {
+    Convert(Volume),
+    Withdraw {
+        units: u32,
+        mode: Mode,
+        is_json: bool,
+    },
+}
+
+impl MilkRequest {
+    // JSON bodies with `units` are withdrawals and any other JSON is a volume
+    // to convert, which is done right away. Any other body withdraws one unit,
+    // unless it's just a number of units.
+    pub fn parse(
+        content_type: &ContentType,
+        body: &str,
+        mode: Option<Mode>,
+    ) -> Result<Self, (StatusCode, String)> {
+        let (units, mode, is_json) = match content_type.or_sniff(body) {
+            Some(MediaType::Json) => match serde_json::from_str::<Withdrawal>(body) {
+                Ok(withdrawal) => (withdrawal.units, withdrawal.mode.or(mode), true),
+                Err(_) => {
+                    return serde_json::from_str::<Conversion>(body)
+                        .map_err(|_| (StatusCode::BAD_REQUEST, String::new()))?
+                        .convert()
+                        .map(MilkRequest::Convert)
+                }
+            },
+            _ => (body.trim().parse().unwrap_or(1), mode, false),
+        };
+
+        if units == 0 {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "units must be at least 1\n".to_string(),
+            ));
+        }
+
+        Ok(MilkRequest::Withdraw {
+            units,
+            mode: mode.unwrap_or_default(),
+            is_json,
+        })
+    }
+}
@@ -0,0 +1,7 @@
+use sqlx::PgPool;
+
+// The indexes come with the table, so `raw_sql` is needed here as in day5.
+pub async fn create_tables(pool: &PgPool) {
+    let sql = include_str!("scheme.sql");
+    sqlx::raw_sql(sql).execute(pool).await.unwrap();
+}
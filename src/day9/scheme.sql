@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS milk_ledger (
+    id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    client TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    units BIGINT NOT NULL,
+    remaining BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS milk_ledger_created_at ON milk_ledger (created_at);
+
+CREATE INDEX IF NOT EXISTS milk_ledger_client ON milk_ledger (client, created_at);
@@ -0,0 +1,245 @@
+use axum::http::StatusCode;
+use serde::{
+    de::{self, MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{fmt, str::FromStr};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Unit {
+    Milliliters,
+    Centiliters,
+    Deciliters,
+    Liters,
+    Litres,
+    CubicMeters,
+    Teaspoons,
+    Tablespoons,
+    FluidOunces,
+    Cups,
+    UsPints,
+    Quarts,
+    Gallons,
+    ImperialFluidOunces,
+    Pints,
+    ImperialQuarts,
+    ImperialGallons,
+}
+
+impl Unit {
+    const ALL: [Unit; 17] = [
+        Unit::Milliliters,
+        Unit::Centiliters,
+        Unit::Deciliters,
+        Unit::Liters,
+        Unit::Litres,
+        Unit::CubicMeters,
+        Unit::Teaspoons,
+        Unit::Tablespoons,
+        Unit::FluidOunces,
+        Unit::Cups,
+        Unit::UsPints,
+        Unit::Quarts,
+        Unit::Gallons,
+        Unit::ImperialFluidOunces,
+        Unit::Pints,
+        Unit::ImperialQuarts,
+        Unit::ImperialGallons,
+    ];
+
+    // The first name is the one responses use. Plain `gallons` have always been
+    // US gallons and plain `pints` imperial ones, so their abbreviations follow.
+    fn names(self) -> &'static [&'static str] {
+        match self {
+            Unit::Milliliters => &[
+                "milliliters",
+                "milliliter",
+                "millilitres",
+                "millilitre",
+                "ml",
+            ],
+            Unit::Centiliters => &[
+                "centiliters",
+                "centiliter",
+                "centilitres",
+                "centilitre",
+                "cl",
+            ],
+            Unit::Deciliters => &["deciliters", "deciliter", "decilitres", "decilitre", "dl"],
+            Unit::Liters => &["liters", "liter", "l"],
+            Unit::Litres => &["litres", "litre"],
+            Unit::CubicMeters => &[
+                "cubic_meters",
+                "cubic_meter",
+                "cubic_metres",
+                "cubic_metre",
+                "m3",
+                "m³",
+            ],
+            Unit::Teaspoons => &["teaspoons", "teaspoon", "tsp"],
+            Unit::Tablespoons => &["tablespoons", "tablespoon", "tbsp"],
+            Unit::FluidOunces => &["fluid_ounces", "fluid_ounce", "us_fluid_ounces", "fl_oz"],
+            Unit::Cups => &["cups", "cup"],
+            Unit::UsPints => &["us_pints", "us_pint"],
+            Unit::Quarts => &["quarts", "quart", "us_quarts", "qt"],
+            Unit::Gallons => &["gallons", "gallon", "us_gallons", "gal"],
+            Unit::ImperialFluidOunces => {
+                &["imperial_fluid_ounces", "imperial_fluid_ounce", "imp_fl_oz"]
+            }
+            Unit::Pints => &["pints", "pint", "imperial_pints", "pt"],
+            Unit::ImperialQuarts => &["imperial_quarts", "imperial_quart"],
+            Unit::ImperialGallons => &["imperial_gallons", "imperial_gallon"],
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        self.names()[0]
+    }
+
+    fn liters(self) -> f64 {
+        const US_GALLON: f64 = 3.785411784;
+        const IMPERIAL_GALLON: f64 = 4.54609;
+
+        match self {
+            Unit::Milliliters => 0.001,
+            Unit::Centiliters => 0.01,
+            Unit::Deciliters => 0.1,
+            Unit::Liters | Unit::Litres => 1.0,
+            Unit::CubicMeters => 1000.0,
+            Unit::Teaspoons => US_GALLON / 768.0,
+            Unit::Tablespoons => US_GALLON / 256.0,
+            Unit::FluidOunces => US_GALLON / 128.0,
+            Unit::Cups => US_GALLON / 16.0,
+            Unit::UsPints => US_GALLON / 8.0,
+            Unit::Quarts => US_GALLON / 4.0,
+            Unit::Gallons => US_GALLON,
+            Unit::ImperialFluidOunces => IMPERIAL_GALLON / 160.0,
+            Unit::Pints => IMPERIAL_GALLON / 8.0,
+            Unit::ImperialQuarts => IMPERIAL_GALLON / 4.0,
+            Unit::ImperialGallons => IMPERIAL_GALLON,
+        }
+    }
+
+    // Where a volume goes when no target is given.
+    fn counterpart(self) -> Option<Unit> {
+        match self {
+            Unit::Gallons => Some(Unit::Liters),
+            Unit::Liters => Some(Unit::Gallons),
+            Unit::Litres => Some(Unit::Pints),
+            Unit::Pints => Some(Unit::Litres),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Unit {
+    type Err = String;
+
+    // Case, and spaces or dashes in place of underscores, don't matter.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s
+            .trim()
+            .to_lowercase()
+            .split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("_");
+
+        Unit::ALL
+            .into_iter()
+            .find(|unit| unit.names().contains(&name.as_str()))
+            .ok_or_else(|| format!("unknown unit `{}`", s.trim()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Unit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Unit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+// Written as a single entry, like `{"liters": 2.5}`.
+#[derive(Clone, Copy, Debug)]
+pub struct Volume {
+    pub amount: f64,
+    pub unit: Unit,
+}
+
+impl Volume {
+    pub fn to(self, unit: Unit) -> Volume {
+        Volume {
+            amount: self.amount * self.unit.liters() / unit.liters(),
+            unit,
+        }
+    }
+}
+
+impl Serialize for Volume {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(self.unit.name(), &self.amount)?;
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Volume {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct VolumeVisitor;
+
+        impl<'de> Visitor<'de> for VolumeVisitor {
+            type Value = Volume;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a single unit and its amount")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Volume, A::Error> {
+                let (unit, amount) = map
+                    .next_entry::<Unit, f64>()?
+                    .ok_or_else(|| de::Error::custom("missing volume"))?;
+                if map.next_key::<de::IgnoredAny>()?.is_some() {
+                    return Err(de::Error::custom("expected a single volume"));
+                }
+
+                Ok(Volume { amount, unit })
+            }
+        }
+
+        deserializer.deserialize_map(VolumeVisitor)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Conversion {
+    #[serde(flatten)]
+    volume: Volume,
+    to: Option<Unit>,
+}
+
+impl Conversion {
+    pub fn convert(self) -> Result<Volume, (StatusCode, String)> {
+        let to = self
+            .to
+            .or_else(|| self.volume.unit.counterpart())
+            .ok_or_else(|| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    format!(
+                        "`to` is required when converting {}",
+                        self.volume.unit.name()
+                    ),
+                )
+            })?;
+
+        Ok(self.volume.to(to))
+    }
+}
@@ -0,0 +1,37 @@
+use serde::{de, Deserialize, Deserializer};
+use std::time::Duration;
+
+const MAX_WAIT: Duration = Duration::from_secs(30);
+
+// Accepts `500ms`, `2s`, `1.5s` or `1m`; a bare number is in seconds.
+pub fn parse_wait<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    let Some(wait) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+
+    let wait = wait.trim();
+    let split = wait
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(wait.len());
+    let (amount, unit) = wait.split_at(split);
+
+    let amount = amount
+        .parse::<f64>()
+        .map_err(|_| de::Error::custom(format!("invalid wait `{wait}`")))?;
+    let seconds = match unit.trim() {
+        "ms" => amount / 1000.0,
+        "s" | "" => amount,
+        "m" => amount * 60.0,
+        unit => return Err(de::Error::custom(format!("unknown wait unit `{unit}`"))),
+    };
+
+    match Duration::try_from_secs_f64(seconds) {
+        Ok(wait) if wait <= MAX_WAIT => Ok(Some(wait)),
+        _ => Err(de::Error::custom(format!(
+            "wait must be between 0s and {}s",
+            MAX_WAIT.as_secs()
+        ))),
+    }
+}
@@ -0,0 +1,118 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts},
+};
+use serde::de::DeserializeOwned;
+use std::convert::Infallible;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MediaType {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl MediaType {
+    // Parameters like `charset` are ignored, since bodies are read as UTF-8
+    // either way.
+    pub fn parse(content_type: &str) -> Option<Self> {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_ascii_lowercase();
+        let (kind, subtype) = essence.split_once('/')?;
+
+        match (kind, subtype) {
+            ("application" | "text", "json" | "x-json") => Some(MediaType::Json),
+            ("application" | "text", "yaml" | "x-yaml") => Some(MediaType::Yaml),
+            ("application" | "text", "toml" | "x-toml") => Some(MediaType::Toml),
+            (_, subtype) => match subtype.rsplit_once('+')?.1 {
+                "json" => Some(MediaType::Json),
+                "yaml" => Some(MediaType::Yaml),
+                "toml" => Some(MediaType::Toml),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let (_, extension) = file_name.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "json" => Some(MediaType::Json),
+            "yaml" | "yml" => Some(MediaType::Yaml),
+            "toml" => Some(MediaType::Toml),
+            _ => None,
+        }
+    }
+
+    // Bare scalars are valid JSON and YAML documents, so only objects, arrays
+    // and mappings count.
+    pub fn sniff(body: &str) -> Option<Self> {
+        let is_json = serde_json::from_str::<serde_json::Value>(body)
+            .is_ok_and(|value| value.is_object() || value.is_array());
+
+        if is_json {
+            Some(MediaType::Json)
+        } else if toml::from_str::<toml::Table>(body).is_ok_and(|table| !table.is_empty()) {
+            Some(MediaType::Toml)
+        } else if serde_yml::from_str::<serde_yml::Mapping>(body)
+            .is_ok_and(|mapping| !mapping.is_empty())
+        {
+            Some(MediaType::Yaml)
+        } else {
+            None
+        }
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(self, body: &str) -> Option<T> {
+        match self {
+            MediaType::Json => serde_json::from_str(body).ok(),
+            MediaType::Yaml => serde_yml::from_str(body).ok(),
+            MediaType::Toml => toml::from_str(body).ok(),
+        }
+    }
+}
+
+pub enum ContentType {
+    Missing,
+    Known(MediaType),
+    Unknown,
+}
+
+impl ContentType {
+    pub fn media_type(&self) -> Option<MediaType> {
+        match self {
+            ContentType::Known(media_type) => Some(*media_type),
+            ContentType::Missing | ContentType::Unknown => None,
+        }
+    }
+
+    // Only a missing header is sniffed; an unknown one was a deliberate choice.
+    pub fn or_sniff(&self, body: &str) -> Option<MediaType> {
+        match self {
+            ContentType::Missing => MediaType::sniff(body),
+            _ => self.media_type(),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ContentType {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let content_type = match parts.headers.get(header::CONTENT_TYPE) {
+            None => ContentType::Missing,
+            Some(content_type) => content_type
+                .to_str()
+                .ok()
+                .and_then(MediaType::parse)
+                .map_or(ContentType::Unknown, ContentType::Known),
+        };
+
+        Ok(content_type)
+    }
+}
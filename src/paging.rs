@@ -0,0 +1,14 @@
+use axum::http::StatusCode;
+
+const MAX_LIMIT: i64 = 100;
+
+// Pages are as large as allowed unless asked otherwise; empty or oversized
+// pages and negative offsets are rejected.
+pub fn limit(limit: Option<i64>, offset: i64) -> Result<i64, StatusCode> {
+    let limit = limit.unwrap_or(MAX_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) || offset < 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    Ok(limit)
+}